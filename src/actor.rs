@@ -1,22 +1,42 @@
-use std::{process, time::Duration};
+use std::{
+    path::{Path, PathBuf},
+    process,
+    time::Duration,
+};
 
 use colored::Colorize;
 use crossterm::execute;
 use crossterm::style::Print;
 use edit;
 use inquire::Select;
+use serde::Serialize;
 
 use crate::cli::Options;
-use crate::{debug_log::DebugLogger, git, jj, openai, util};
+use crate::output::{self, status};
+use crate::pricing::{self, Pricing};
+use crate::session::Session;
+use crate::{
+    cache, debug_log::DebugLogger, git, hook, jj, ledger, lint, openai, patch, trailers, util,
+};
 
 pub struct Actor {
     messages: Vec<openai::Message>,
     options: Options,
     api_key: String,
     pub used_tokens: usize,
+    /// Hash of the changes being described, saved with the session
+    pub diff_hash: String,
+    /// Ticket IDs from the branch name, added in `trailer`/`prefix` mode
+    pub tickets: Vec<String>,
+    /// Trailers appended to the chosen message
+    pub trailers: Vec<String>,
+    /// What the response schema asks the title to be
+    pub title_description: String,
     api_endpoint: String,
     debug_logger: DebugLogger,
     vcs_type: jj::VcsType,
+    /// Requests of this run, recorded in the usage ledger once its outcome is known
+    requests: Vec<ledger::Entry>,
 }
 
 impl Actor {
@@ -26,16 +46,67 @@ impl Actor {
         api_endpoint: String,
         vcs_type: jj::VcsType,
     ) -> Self {
-        // Get debug_file before moving options
-        let debug_file = options.debug_file.clone();
+        // Create the logger before moving options
+        let debug_logger =
+            DebugLogger::new(options.debug_file.clone(), &options.debug_log).with_secret(&api_key);
         Self {
             messages: Vec::new(),
+            title_description: options.convention.title_description(),
             options,
             api_key,
             used_tokens: 0,
+            diff_hash: String::new(),
+            tickets: Vec::new(),
+            trailers: Vec::new(),
             api_endpoint,
-            debug_logger: DebugLogger::new(debug_file),
+            debug_logger,
             vcs_type,
+            requests: Vec::new(),
+        }
+    }
+
+    fn record_request(&mut self, result: &openai::CompletionResult) {
+        let usage = result.usage.as_ref();
+        let mut entry = ledger::Entry {
+            timestamp: ledger::timestamp(),
+            model: self.options.model.0.clone(),
+            repo: match self.vcs_type {
+                jj::VcsType::Patch => String::new(),
+                _ => jj::repo_root()
+                    // Without git's trailing slash, so jj and git runs group together
+                    .map(|root| root.components().collect::<PathBuf>())
+                    .map(|root| root.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            },
+            prompt_tokens: usage.map_or(self.used_tokens, |u| u.prompt_tokens),
+            cached_tokens: usage.map_or(0, |u| u.prompt_tokens_details.cached_tokens),
+            completion_tokens: usage.map_or(0, |u| u.completion_tokens),
+            reasoning_tokens: usage.map_or(0, |u| u.completion_tokens_details.reasoning_tokens),
+            duration_ms: result.duration.as_millis(),
+            outcome: String::new(),
+            cost_usd: None,
+        };
+        entry.cost_usd = Some(entry.cost(&self.options.pricing));
+        self.requests.push(entry);
+    }
+
+    /// Records the requests of this run in the usage ledger with how it ended
+    fn finish(&mut self, outcome: &str) {
+        let mut requests = std::mem::take(&mut self.requests);
+        for request in &mut requests {
+            request.outcome = outcome.to_string();
+        }
+        if let Err(err) = self.options.ledger.append(&requests) {
+            self.debug_logger
+                .log_error(&format!("writing the usage ledger failed: {}", err));
+        }
+    }
+
+    /// `committed`, or `printed` when there is no repository to commit to
+    fn committed(&self) -> &'static str {
+        match self.vcs_type {
+            jj::VcsType::Patch => "printed",
+            _ => "committed",
         }
     }
 
@@ -49,9 +120,22 @@ impl Actor {
             return;
         }
 
-        println!("\n{}", "Generated Commit Messages:".blue().bold());
+        status!("\n{}", "Generated Commit Messages:".blue().bold());
+        self.print_usage(usage, duration);
+
+        for (i, suggestion) in suggestions.iter().enumerate() {
+            status!(
+                "[{}] {}\n{}\n",
+                format!("{i}").purple(),
+                "=".repeat(77 - i.to_string().len()),
+                suggestion.as_commit_message()
+            );
+        }
+    }
+
+    fn print_usage(&self, usage: Option<&openai::Usage>, duration: Duration) {
         if let Some(usage) = usage {
-            println!(
+            status!(
                 "{} {} in, {} out (total: {}) | {} {}",
                 "Tokens:".bright_black(),
                 util::format_token_count(usage.prompt_tokens).purple(),
@@ -61,7 +145,7 @@ impl Actor {
                 format!("{:.1}s", duration.as_secs_f32()).purple()
             );
         } else {
-            println!(
+            status!(
                 "{} {} in (model usage unavailable) | {} {}",
                 "Tokens:".bright_black(),
                 util::format_token_count(self.used_tokens).purple(),
@@ -69,38 +153,192 @@ impl Actor {
                 format!("{:.1}s", duration.as_secs_f32()).purple()
             );
         }
-
-        for (i, suggestion) in suggestions.iter().enumerate() {
-            println!(
-                "[{}] {}\n{}\n",
-                format!("{i}").purple(),
-                "=".repeat(77 - i.to_string().len()),
-                suggestion.as_commit_message()
-            );
-        }
     }
 
     pub fn add_message(&mut self, message: openai::Message) {
         // Log message content if debug_context is enabled
         if self.options.debug_context {
-            println!("\n{}", "=== Message to AI ===".blue().bold());
-            println!(
+            status!("\n{}", "=== Message to AI ===".blue().bold());
+            status!(
                 "{}: {}",
                 format!("{:?}", message.role).purple().bold(),
                 message.content.bright_black()
             );
-            println!("{}", "=====================".blue().bold());
+            status!("{}", "=====================".blue().bold());
         }
         self.messages.push(message);
     }
 
     async fn ask(&mut self) -> anyhow::Result<openai::CompletionResult> {
-        let suggestion_count = self.options.n.max(1) as usize;
+        let mut result = self.complete(self.messages.clone()).await?;
+        let mut usage = result.usage.clone();
+        let mut duration = result.duration;
+
+        // Footers that will be appended as trailers don't have to come from the model
+        let mut lint = self.options.lint.clone();
+        lint.footers_required.retain(|token| {
+            !self
+                .trailers
+                .iter()
+                .any(|t| trailers::parse(t).is_some_and(|(key, _)| key == token))
+        });
+
+        // Repair what can be fixed locally, send the rest back to the model
+        let mut rounds = 0;
+        let violations = loop {
+            let violations: Vec<(usize, Vec<lint::Violation>)> = result
+                .suggestions
+                .iter_mut()
+                .enumerate()
+                .map(|(i, suggestion)| {
+                    self.options
+                        .tickets
+                        .apply(&self.options.convention, suggestion, &self.tickets);
+                    let mut violations = lint.repair(&self.options.convention, suggestion);
+                    violations.extend(lint.check_language(
+                        &self.options.language,
+                        &self.options.convention,
+                        suggestion,
+                    ));
+                    (i, violations)
+                })
+                .filter(|(_, violations)| !violations.is_empty())
+                .collect();
+            if violations.is_empty() || rounds >= lint.max_repair_rounds {
+                break violations;
+            }
+            rounds += 1;
+
+            let correction = lint::LintConfig::correction_prompt(&violations);
+            self.debug_logger
+                .log_info(&format!("lint round {}: {}", rounds, correction));
+            // Each round is another paid request
+            status!(
+                "{} {}",
+                "Lint:".yellow(),
+                format!(
+                    "sending {} suggestion(s) back to fix their violations (round {} of {})",
+                    violations.len(),
+                    rounds,
+                    lint.max_repair_rounds
+                )
+                .bright_black()
+            );
+            if self.options.debug {
+                status!("\n{}", "=== Lint Violations ===".yellow().bold());
+                status!("{}", correction.bright_black());
+            }
+
+            let mut messages = self.messages.clone();
+            messages.push(openai::Message::assistant(serde_json::to_string(
+                &openai::CommitSuggestionsEnvelope {
+                    suggestions: result.suggestions.clone(),
+                },
+            )?));
+            messages.push(openai::Message::user(correction));
+            result = self.complete(messages).await?;
+            usage = match (usage, &result.usage) {
+                (Some(mut total), Some(round)) => {
+                    total.add(round);
+                    Some(total)
+                }
+                (total, round) => total.or_else(|| round.clone()),
+            };
+            duration += result.duration;
+        };
+        // Report what all rounds used, not only the last
+        result.usage = usage;
+        result.duration = duration;
+
+        if result.cached {
+            status!(
+                "{}",
+                "Reusing the response to an identical request, run with --no-cache for new suggestions."
+                    .bright_black()
+            );
+        }
+        if output::is_machine_readable() {
+            self.print_usage(result.usage.as_ref(), result.duration);
+        } else {
+            self.print_suggestions(&result.suggestions, result.usage.as_ref(), result.duration);
+        }
+        for (i, list) in &violations {
+            for violation in list {
+                status!(
+                    "{} [{}] {}",
+                    "Lint:".yellow(),
+                    i.to_string().purple(),
+                    violation.to_string().bright_black()
+                );
+            }
+        }
+        Ok(result)
+    }
+
+    /// Holds a conversation to the token and spending limits of the budget,
+    /// counting what this run has spent so far. Returns the conversation with
+    /// a reduced diff when files were left out.
+    fn enforce_budget(
+        &mut self,
+        messages: &[openai::Message],
+    ) -> anyhow::Result<Option<Vec<openai::Message>>> {
+        let Some(limit) = self.options.budget.current_limit(
+            &self.options.pricing,
+            &self.options.model.0,
+            &self.options.ledger,
+            &self.requests,
+            self.options.n.max(1) as usize,
+        )?
+        else {
+            return Ok(None);
+        };
+        let tokens = messages
+            .iter()
+            .map(|message| openai::count_token(&message.content))
+            .collect::<anyhow::Result<Vec<usize>>>()?;
+        let total: usize = tokens.iter().sum();
+        if total <= limit.tokens {
+            return Ok(None);
+        }
+
+        // Only the diff can be reduced
+        let diff = messages
+            .iter()
+            .position(|message| !patch::files(&message.content).is_empty());
+        let (used, content, content_tokens) = match diff {
+            Some(i) => (total - tokens[i], messages[i].content.clone(), tokens[i]),
+            None => (total, String::new(), 0),
+        };
+        let (content, content_tokens) =
+            match self
+                .options
+                .budget
+                .enforce(Some(&limit), used, content, content_tokens)
+            {
+                Ok(diff) => diff,
+                Err(err) => {
+                    self.finish("aborted");
+                    status!("{}", err.to_string().red());
+                    process::exit(1);
+                }
+            };
+        let Some(i) = diff.filter(|i| messages[*i].content != content) else {
+            return Ok(None);
+        };
+        let mut messages = messages.to_vec();
+        messages[i].content = content;
+        self.used_tokens = used + content_tokens;
+        Ok(Some(messages))
+    }
+
+    /// Builds the request for a conversation with the model options
+    fn request(&self, messages: Vec<openai::Message>) -> openai::Request {
         let mut request = openai::Request::new(
             self.options.model.clone().to_string(),
-            self.messages.clone(),
-            suggestion_count,
-        );
+            messages,
+            self.options.n.max(1) as usize,
+        )
+        .with_title_description(&self.title_description);
 
         // Add reasoning effort (default from config or CLI override)
         if let Some(ref effort) = self.options.reasoning_effort {
@@ -111,10 +349,43 @@ impl Actor {
         if let Some(ref verbosity) = self.options.verbosity {
             request = request.with_verbosity(Some(verbosity.clone()));
         }
+        request
+    }
+
+    /// Sends one request for the given conversation and parses the suggestions
+    async fn complete(
+        &mut self,
+        messages: Vec<openai::Message>,
+    ) -> anyhow::Result<openai::CompletionResult> {
+        let suggestion_count = self.options.n.max(1) as usize;
+        let message_count = messages.len();
+
+        // Reuse the response to an identical request, e.g. after an aborted selection
+        let mut request = self.request(messages.clone());
+        let mut json = serde_json::to_string(&request)?;
+        let mut cache_key = cache::key(&self.api_endpoint, suggestion_count, &json);
+        let cached = match self.options.cache.is_active() {
+            true => self.options.cache.get(&cache_key),
+            false => None,
+        };
+
+        // Cached responses cost nothing, everything else is held to the budget
+        if cached.is_none() {
+            if let Some(messages) = self.enforce_budget(&messages)? {
+                request = self.request(messages);
+                json = serde_json::to_string(&request)?;
+                cache_key = cache::key(&self.api_endpoint, suggestion_count, &json);
+            }
+        }
 
         // Log request details
-        let json = serde_json::to_string(&request)?;
+        self.debug_logger.begin_request();
         self.debug_logger.log_request(&json);
+        if let Some(result) = cached {
+            self.debug_logger
+                .log_info(&format!("cache hit: {}", cache_key));
+            return Ok(result);
+        }
 
         // Log basic info about the request
         let info = format!(
@@ -125,20 +396,20 @@ impl Actor {
                 .as_deref()
                 .unwrap_or("default"),
             self.options.verbosity.as_deref().unwrap_or("default"),
-            self.messages.len(),
+            message_count,
             self.used_tokens
         );
         self.debug_logger.log_info(&info);
 
         // Show useful info in debug mode
         if self.options.debug && self.options.debug_file.is_none() {
-            println!("\n{}", "=== Request Info ===".blue().bold());
-            println!(
+            status!("\n{}", "=== Request Info ===".blue().bold());
+            status!(
                 "  {}: {}",
                 "Model".bright_black(),
                 self.options.model.0.purple()
             );
-            println!(
+            status!(
                 "  {}: {}",
                 "Reasoning Effort".bright_black(),
                 self.options
@@ -147,7 +418,7 @@ impl Actor {
                     .unwrap_or("default")
                     .purple()
             );
-            println!(
+            status!(
                 "  {}: {}",
                 "Verbosity".bright_black(),
                 self.options
@@ -156,12 +427,12 @@ impl Actor {
                     .unwrap_or("default")
                     .purple()
             );
-            println!(
+            status!(
                 "  {}: {}",
                 "Messages".bright_black(),
-                self.messages.len().to_string().purple()
+                message_count.to_string().purple()
             );
-            println!(
+            status!(
                 "  {}: {}",
                 "Input Tokens".bright_black(),
                 self.used_tokens.to_string().purple()
@@ -183,7 +454,13 @@ impl Actor {
                     "success: generated {} suggestions",
                     result.suggestions.len()
                 ));
-                self.print_suggestions(&result.suggestions, result.usage.as_ref(), result.duration);
+                self.record_request(&result);
+                if self.options.cache.is_active() {
+                    if let Err(err) = self.options.cache.put(&cache_key, &result) {
+                        self.debug_logger
+                            .log_error(&format!("cache write failed: {}", err));
+                    }
+                }
                 Ok(result)
             }
             Err(e) => {
@@ -196,7 +473,7 @@ impl Actor {
 
     pub async fn start(&mut self) -> anyhow::Result<()> {
         if self.options.debug {
-            println!("\n{}", "=== Starting Commit Generation ===".blue().bold());
+            status!("\n{}", "=== Starting Commit Generation ===".blue().bold());
         }
 
         let suggestions = self.ask().await?.suggestions;
@@ -210,15 +487,80 @@ impl Actor {
             return Err(anyhow::anyhow!("No commit suggestions were generated"));
         }
 
-        let mut message = match util::choose_message(formatted_first_choices) {
-            Some(message) => message,
+        let message = match util::choose_message(formatted_first_choices) {
+            Some(message) => trailers::append(&message, &self.trailers),
             None => {
                 if self.options.debug {
-                    println!("{}", "User cancelled message selection".yellow());
+                    status!("{}", "User cancelled message selection".yellow());
                 }
                 return Ok(());
             }
         };
+        self.review(message).await
+    }
+
+    /// Continues the Commit/Edit/Revise loop of a saved session
+    pub async fn resume(&mut self, session: Session) -> anyhow::Result<()> {
+        self.messages = session.messages;
+        self.trailers = session.trailers;
+        self.tickets = session.tickets;
+        self.diff_hash = session.diff_hash;
+        self.used_tokens = session.used_tokens;
+        execute!(
+            std::io::stdout(),
+            Print(format!(
+                "{}\n",
+                format!("[{}]=======", "Resumed Message".purple()).bright_black()
+            )),
+            Print(&session.message),
+            Print(format!("\n{}\n", "=======================".bright_black())),
+        )?;
+        self.review(session.message).await
+    }
+
+    /// Saves the conversation and the candidate message for `--resume`
+    fn save_session(&mut self, message: &str) {
+        let Some(path) = Session::path(&self.vcs_type) else {
+            return;
+        };
+        let session = Session {
+            messages: self.messages.clone(),
+            message: message.to_string(),
+            trailers: self.trailers.clone(),
+            tickets: self.tickets.clone(),
+            amend: self.options.amend,
+            jj_revision: self.options.jj_revision.clone(),
+            diff_hash: self.diff_hash.clone(),
+            used_tokens: self.used_tokens,
+        };
+        if let Err(err) = session.save_to(&path) {
+            self.debug_logger
+                .log_error(&format!("saving the session failed: {}", err));
+        }
+    }
+
+    fn clear_session(&self) {
+        if let Some(path) = Session::path(&self.vcs_type) {
+            Session::clear(&path);
+        }
+    }
+
+    /// Exits after a failed commit, keeping the session to retry with `--resume`
+    fn exit_resumable(&mut self, error: anyhow::Error) -> ! {
+        self.finish("failed");
+        status!("{error}");
+        if Session::path(&self.vcs_type).is_some_and(|path| path.exists()) {
+            status!(
+                "{} {}",
+                "The message is saved.".bright_black(),
+                "Run `turbocommit --resume` to try again.".purple()
+            );
+        }
+        process::exit(1);
+    }
+
+    /// Asks what to do with the chosen message until it is committed or aborted
+    async fn review(&mut self, mut message: String) -> anyhow::Result<()> {
         let tasks = vec![
             Task::Commit.to_str(),
             Task::Edit.to_str(),
@@ -227,23 +569,20 @@ impl Actor {
         ];
 
         loop {
+            self.save_session(&message);
             let task = Select::new("What to do with the message?", tasks.clone()).prompt()?;
 
             match Task::from_str(task) {
                 Task::Commit => {
                     if self.options.debug {
-                        println!("\n{}", "=== Committing ===".blue().bold());
+                        status!("\n{}", "=== Committing ===".blue().bold());
                     }
                     match self.vcs_type {
                         jj::VcsType::Git => {
-                            match git::commit(message, self.options.amend) {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    println!("{e}");
-                                    process::exit(1);
-                                }
-                            };
-                            println!(
+                            if let Err(e) = git::commit(message, self.options.amend) {
+                                self.exit_resumable(e);
+                            }
+                            status!(
                                 "{} 🎉",
                                 if self.options.amend {
                                     "Commit message amended!"
@@ -254,24 +593,23 @@ impl Actor {
                             );
                         }
                         jj::VcsType::Jujutsu => {
-                            match jj::set_jj_description(
+                            if let Err(e) = jj::set_jj_description(
                                 self.options.jj_revision.as_deref(),
                                 &message,
                             ) {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    println!("{e}");
-                                    process::exit(1);
-                                }
-                            };
-                            println!("{} 🎉", "Description set successfully!".purple());
+                                self.exit_resumable(e);
+                            }
+                            status!("{} 🎉", "Description set successfully!".purple());
                         }
+                        jj::VcsType::Patch => println!("{}", message),
                     }
+                    self.clear_session();
+                    self.finish(self.committed());
                     break;
                 }
                 Task::Edit => {
                     if self.options.debug {
-                        println!("\n{}", "=== Opening Editor ===".blue().bold());
+                        status!("\n{}", "=== Opening Editor ===".blue().bold());
                     }
                     message = edit::edit(message)?;
                     execute!(
@@ -286,12 +624,12 @@ impl Actor {
                 }
                 Task::Revise => {
                     if self.options.debug {
-                        println!("\n{}", "=== Revising Message ===".blue().bold());
+                        status!("\n{}", "=== Revising Message ===".blue().bold());
                     }
                     self.add_message(openai::Message::assistant(message.clone()));
                     let input = inquire::Text::new("Revise:").prompt()?;
                     if self.options.debug {
-                        println!("  User input: {}", input.bright_black());
+                        status!("  User input: {}", input.bright_black());
                     }
                     self.add_message(openai::Message::user(input));
 
@@ -303,7 +641,7 @@ impl Actor {
                         .collect();
 
                     if revision_choices.is_empty() {
-                        println!("{}", "No revision suggestions produced".yellow());
+                        status!("{}", "No revision suggestions produced".yellow());
                         continue;
                     }
 
@@ -314,10 +652,10 @@ impl Actor {
                     );
 
                     message = match util::choose_message(revision_choices) {
-                        Some(message) => message,
+                        Some(message) => trailers::append(&message, &self.trailers),
                         None => {
                             if self.options.debug {
-                                println!("{}", "User cancelled message selection".yellow());
+                                status!("{}", "User cancelled message selection".yellow());
                             }
                             return Ok(());
                         }
@@ -325,8 +663,10 @@ impl Actor {
                 }
                 Task::Abort => {
                     if self.options.debug {
-                        println!("\n{}", "=== Aborted ===".yellow().bold());
+                        status!("\n{}", "=== Aborted ===".yellow().bold());
                     }
+                    self.clear_session();
+                    self.finish("aborted");
                     break;
                 }
             }
@@ -335,26 +675,189 @@ impl Actor {
         Ok(())
     }
 
+    /// Prints the suggestions as `json` or `plain` text instead of asking what to do
+    pub async fn report(&mut self, format: &str) -> anyhow::Result<()> {
+        let result = self.ask().await?;
+        let messages: Vec<String> = result
+            .suggestions
+            .iter()
+            .map(|s| trailers::append(&s.as_commit_message(), &self.trailers))
+            .collect();
+
+        if format == "json" {
+            let report = Report {
+                model: &self.options.model.0,
+                suggestions: result
+                    .suggestions
+                    .iter()
+                    .zip(&messages)
+                    .map(|(suggestion, message)| ReportSuggestion {
+                        title: &suggestion.title,
+                        body: suggestion.body.as_deref(),
+                        message,
+                    })
+                    .collect(),
+                usage: result.usage.as_ref(),
+                duration_ms: result.duration.as_millis(),
+            };
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            // Messages can contain blank lines, so separate them with a marker line
+            println!("{}", messages.join("\n---\n"));
+        }
+        self.finish("printed");
+        Ok(())
+    }
+
+    /// Prints the messages the first request would send, with token counts and
+    /// the estimated cost, without calling the API
+    pub fn dry_run(&self, format: Option<&str>) -> anyhow::Result<()> {
+        let suggestions = self.options.n.max(1) as usize;
+        let messages = self
+            .messages
+            .iter()
+            .map(|message| {
+                Ok(DryRunMessage {
+                    role: &message.role,
+                    tokens: openai::count_token(&message.content)?,
+                    content: &message.content,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let input_tokens = messages.iter().map(|m| m.tokens).sum();
+        let output_tokens = Pricing::estimated_output(suggestions);
+        let report = DryRun {
+            model: &self.options.model.0,
+            suggestions,
+            reasoning_effort: self.options.reasoning_effort.as_deref(),
+            verbosity: self.options.verbosity.as_deref(),
+            input_tokens,
+            estimated_output_tokens: output_tokens,
+            estimated_cost: self.options.pricing.for_model(&self.options.model.0).cost(
+                input_tokens,
+                0,
+                output_tokens,
+            ),
+            messages,
+        };
+
+        if format == Some("json") {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        for (i, message) in report.messages.iter().enumerate() {
+            println!(
+                "[{}] {} {}\n{}\n",
+                format!("{i}").purple(),
+                message.role.to_string().blue().bold(),
+                format!("({} tokens)", util::format_token_count(message.tokens)).bright_black(),
+                message.content
+            );
+        }
+        println!(
+            "{} {} | {} {} | {} {} in, ~{} out | {} ~{}",
+            "Model:".bright_black(),
+            report.model.purple(),
+            "Suggestions:".bright_black(),
+            report.suggestions.to_string().purple(),
+            "Tokens:".bright_black(),
+            util::format_token_count(input_tokens).purple(),
+            util::format_token_count(output_tokens).purple(),
+            "Cost:".bright_black(),
+            pricing::format_cost(report.estimated_cost).purple()
+        );
+        println!("{}", "Dry run, nothing was sent.".bright_black());
+        Ok(())
+    }
+
+    /// Writes the first suggestion into git's message file for the
+    /// prepare-commit-msg hook; git commits it after the editor closes
+    pub async fn write_message(&mut self, file: &Path) -> anyhow::Result<()> {
+        let result = self.ask().await?;
+        let Some(suggestion) = result.suggestions.first() else {
+            anyhow::bail!("No commit message generated");
+        };
+        let message = trailers::append(&suggestion.as_commit_message(), &self.trailers);
+        hook::write_message(file, &message)?;
+        self.finish("printed");
+        Ok(())
+    }
+
     pub async fn auto_commit(&mut self) -> anyhow::Result<String> {
         let completion = self.ask().await?;
         if completion.suggestions.is_empty() {
             return Err(anyhow::anyhow!("No commit message generated"));
         }
-        let message = completion.suggestions[0].as_commit_message();
+        let message = trailers::append(
+            &completion.suggestions[0].as_commit_message(),
+            &self.trailers,
+        );
 
-        match self.vcs_type {
-            jj::VcsType::Git => {
-                git::commit(message.clone(), self.options.amend)?;
-            }
+        let committed = match self.vcs_type {
+            jj::VcsType::Git => git::commit(message.clone(), self.options.amend),
             jj::VcsType::Jujutsu => {
-                jj::set_jj_description(self.options.jj_revision.as_deref(), &message)?;
+                jj::set_jj_description(self.options.jj_revision.as_deref(), &message)
+            }
+            jj::VcsType::Patch => {
+                println!("{}", message);
+                Ok(())
             }
+        };
+        if let Err(err) = committed {
+            self.finish("failed");
+            return Err(err);
         }
+        self.finish(self.committed());
 
         Ok(message)
     }
 }
 
+// Runs that end early, e.g. on a cancelled prompt or an error, count as aborted
+impl Drop for Actor {
+    fn drop(&mut self) {
+        self.finish("aborted");
+    }
+}
+
+/// The `--dry-run --format json` output
+#[derive(Serialize)]
+struct DryRun<'a> {
+    model: &'a str,
+    suggestions: usize,
+    reasoning_effort: Option<&'a str>,
+    verbosity: Option<&'a str>,
+    input_tokens: usize,
+    estimated_output_tokens: usize,
+    /// USD, from the configured `pricing`
+    estimated_cost: f64,
+    messages: Vec<DryRunMessage<'a>>,
+}
+
+#[derive(Serialize)]
+struct DryRunMessage<'a> {
+    role: &'a openai::Role,
+    tokens: usize,
+    content: &'a str,
+}
+
+/// The `--format json` output
+#[derive(Serialize)]
+struct Report<'a> {
+    model: &'a str,
+    suggestions: Vec<ReportSuggestion<'a>>,
+    usage: Option<&'a openai::Usage>,
+    duration_ms: u128,
+}
+
+#[derive(Serialize)]
+struct ReportSuggestion<'a> {
+    title: &'a str,
+    body: Option<&'a str>,
+    /// The full commit message, including trailers
+    message: &'a str,
+}
+
 enum Task {
     Commit,
     Edit,
@@ -0,0 +1,402 @@
+use chrono::{Datelike, Local, NaiveDate};
+use colored::Colorize;
+use inquire::Select;
+use serde::{Deserialize, Serialize};
+
+use crate::ledger::{self, LedgerConfig};
+use crate::output::{self, status};
+use crate::pricing::{self, Pricing};
+use crate::{openai, patch};
+
+/// What `on_exceed` accepts
+pub const ON_EXCEED: &[&str] = &["prompt", "reduce", "abort"];
+
+/// Limits on what a single request and all requests of a day or month may cost
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct BudgetConfig {
+    /// Input tokens of a single request, 0 for no limit
+    pub max_input_tokens: usize,
+    /// Estimated cost of a single request in USD, 0 for no limit
+    pub max_request_cost: f64,
+    /// Spending per calendar day in USD according to the usage ledger, 0 for no limit
+    pub daily_limit: f64,
+    /// Spending per calendar month in USD according to the usage ledger, 0 for no limit
+    pub monthly_limit: f64,
+    /// `prompt`, `reduce` (leave out the largest files) or `abort`
+    pub on_exceed: String,
+}
+
+impl Default for BudgetConfig {
+    fn default() -> Self {
+        Self {
+            max_input_tokens: 0,
+            max_request_cost: 0.0,
+            daily_limit: 0.0,
+            monthly_limit: 0.0,
+            on_exceed: String::from("prompt"),
+        }
+    }
+}
+
+/// The most input tokens a request may have and the limit that sets it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limit {
+    pub tokens: usize,
+    pub reason: String,
+}
+
+impl BudgetConfig {
+    /// Whether all amounts are finite and not negative
+    pub fn is_valid(&self) -> bool {
+        [self.max_request_cost, self.daily_limit, self.monthly_limit]
+            .iter()
+            .all(|amount| amount.is_finite() && *amount >= 0.0)
+    }
+
+    /// The tightest limit for a `model` request asking for `suggestions`
+    /// suggestions, given what the ledger `entries` spent so far
+    pub fn limit(
+        &self,
+        pricing: &Pricing,
+        model: &str,
+        entries: &[ledger::Entry],
+        suggestions: usize,
+        today: NaiveDate,
+    ) -> Option<Limit> {
+        // Input tokens left after the expected output is paid for
+        let prices = pricing.for_model(model);
+        let output_cost = prices.cost(0, 0, Pricing::estimated_output(suggestions));
+        let tokens_for = |cost: f64| {
+            if cost <= output_cost {
+                0
+            } else if prices.input <= 0.0 {
+                usize::MAX
+            } else {
+                ((cost - output_cost) * 1_000_000.0 / prices.input) as usize
+            }
+        };
+        let spent = |same_period: &dyn Fn(NaiveDate) -> bool| -> f64 {
+            entries
+                .iter()
+                .filter(|entry| entry.date().is_some_and(same_period))
+                .map(|entry| entry.cost(pricing))
+                .sum()
+        };
+
+        let mut limits = Vec::new();
+        if self.max_input_tokens > 0 {
+            limits.push(Limit {
+                tokens: self.max_input_tokens,
+                reason: format!("budget.max_input_tokens is {}", self.max_input_tokens),
+            });
+        }
+        if self.max_request_cost > 0.0 {
+            limits.push(Limit {
+                tokens: tokens_for(self.max_request_cost),
+                reason: format!(
+                    "budget.max_request_cost is {}",
+                    pricing::format_cost(self.max_request_cost)
+                ),
+            });
+        }
+        for (cap, period, same_period) in [
+            (
+                self.daily_limit,
+                "daily",
+                &(|date: NaiveDate| date == today) as &dyn Fn(NaiveDate) -> bool,
+            ),
+            (self.monthly_limit, "monthly", &|date: NaiveDate| {
+                (date.year(), date.month()) == (today.year(), today.month())
+            }),
+        ] {
+            if cap > 0.0 {
+                let left = (cap - spent(same_period)).max(0.0);
+                limits.push(Limit {
+                    tokens: tokens_for(left),
+                    reason: format!(
+                        "{} of the {} limit of {} is left",
+                        pricing::format_cost(left),
+                        period,
+                        pricing::format_cost(cap)
+                    ),
+                });
+            }
+        }
+        let mut limit = limits.into_iter().min_by_key(|limit| limit.tokens)?;
+        if limit.tokens == 0 {
+            limit
+                .reason
+                .push_str(", not enough for the expected output");
+        }
+        Some(limit)
+    }
+
+    /// The tightest limit right now, counting the `run` requests not yet in the
+    /// ledger and reading the ledger only when a spending cap is set
+    pub fn current_limit(
+        &self,
+        pricing: &Pricing,
+        model: &str,
+        ledger: &LedgerConfig,
+        run: &[ledger::Entry],
+        suggestions: usize,
+    ) -> anyhow::Result<Option<Limit>> {
+        let entries = if self.daily_limit > 0.0 || self.monthly_limit > 0.0 {
+            let mut entries = ledger::read(&ledger.path())?;
+            entries.extend_from_slice(run);
+            entries
+        } else {
+            Vec::new()
+        };
+        Ok(self.limit(
+            pricing,
+            model,
+            &entries,
+            suggestions,
+            Local::now().date_naive(),
+        ))
+    }
+
+    /// Checks a request of `used_tokens` plus the diff against the limit and
+    /// returns the diff to send, reduced or confirmed as `on_exceed` says
+    pub fn enforce(
+        &self,
+        limit: Option<&Limit>,
+        used_tokens: usize,
+        diff: String,
+        diff_tokens: usize,
+    ) -> anyhow::Result<(String, usize)> {
+        let Some(limit) = limit else {
+            return Ok((diff, diff_tokens));
+        };
+        if used_tokens + diff_tokens <= limit.tokens {
+            return Ok((diff, diff_tokens));
+        }
+        let message = format!(
+            "The request is ~{} tokens long, over the budget of {} tokens ({}).",
+            used_tokens + diff_tokens,
+            limit.tokens,
+            limit.reason
+        );
+
+        const SEND: &str = "Send it anyway";
+        const REDUCE: &str = "Leave out the largest files";
+        const ABORT: &str = "Abort";
+        let action = match self.on_exceed.as_str() {
+            "prompt" if !output::is_machine_readable() => {
+                status!("{}", message.yellow());
+                Select::new("What to do?", vec![SEND, REDUCE, ABORT]).prompt()?
+            }
+            "reduce" => REDUCE,
+            _ => ABORT,
+        };
+        match action {
+            SEND => Ok((diff, diff_tokens)),
+            REDUCE => {
+                let Some((reduced, tokens, left_out)) =
+                    reduce(&diff, limit.tokens.saturating_sub(used_tokens))?
+                else {
+                    anyhow::bail!("{} Not even the smallest file fits.", message);
+                };
+                status!(
+                    "{} {}",
+                    "Left out to stay within the budget:".yellow(),
+                    left_out.join(", ").bright_black()
+                );
+                Ok((reduced, tokens))
+            }
+            _ => anyhow::bail!(
+                "{} Stage fewer changes or raise the limits under `budget` in the config.",
+                message
+            ),
+        }
+    }
+}
+
+/// Keeps the smallest files of the diff that fit into `max_tokens` in their
+/// original order, and returns the reduced diff, its tokens and the files left out
+fn reduce(diff: &str, max_tokens: usize) -> anyhow::Result<Option<(String, usize, Vec<String>)>> {
+    let sections = patch::split(diff);
+    let mut sizes = Vec::new();
+    for (i, (_, section)) in sections.iter().enumerate() {
+        sizes.push((openai::count_token(section)?, i));
+    }
+    sizes.sort();
+
+    let mut kept = vec![false; sections.len()];
+    let mut total = 0;
+    for (tokens, i) in sizes {
+        if total + tokens > max_tokens {
+            break;
+        }
+        total += tokens;
+        kept[i] = true;
+    }
+    if !kept.contains(&true) {
+        return Ok(None);
+    }
+
+    let mut reduced = String::new();
+    let mut left_out = Vec::new();
+    for ((file, section), kept) in sections.into_iter().zip(kept) {
+        if kept {
+            reduced.push_str(&section);
+        } else {
+            left_out.push(file);
+        }
+    }
+    let tokens = openai::count_token(&reduced)?;
+    Ok(Some((reduced, tokens, left_out)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: &str, prompt_tokens: usize) -> ledger::Entry {
+        ledger::Entry {
+            timestamp: timestamp.to_string(),
+            model: "gpt-5.4".to_string(),
+            repo: String::new(),
+            prompt_tokens,
+            cached_tokens: 0,
+            completion_tokens: 0,
+            reasoning_tokens: 0,
+            duration_ms: 0,
+            outcome: "committed".to_string(),
+            cost_usd: None,
+        }
+    }
+
+    #[test]
+    fn test_limit() {
+        let pricing = Pricing::default();
+        let today = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        assert_eq!(
+            BudgetConfig::default().limit(&pricing, "gpt-5.4", &[], 3, today),
+            None
+        );
+
+        // Without suggestions no output cost is expected
+        let budget = BudgetConfig {
+            max_input_tokens: 50_000,
+            max_request_cost: 0.05,
+            ..BudgetConfig::default()
+        };
+        let limit = budget.limit(&pricing, "gpt-5.4", &[], 0, today).unwrap();
+        assert_eq!(limit.tokens, 20_000);
+        assert!(
+            budget
+                .limit(&pricing, "gpt-5.4", &[], 3, today)
+                .unwrap()
+                .tokens
+                < 20_000
+        );
+        assert!(limit.reason.contains("max_request_cost"));
+
+        // $2.50 was spent today and $5 earlier this month
+        let entries = vec![
+            entry("2026-10-18T09:00:00+02:00", 1_000_000),
+            entry("2026-10-02T09:00:00+02:00", 2_000_000),
+            entry("2026-09-30T09:00:00+02:00", 4_000_000),
+        ];
+        let budget = BudgetConfig {
+            daily_limit: 3.0,
+            monthly_limit: 20.0,
+            ..BudgetConfig::default()
+        };
+        let limit = budget
+            .limit(&pricing, "gpt-5.4", &entries, 0, today)
+            .unwrap();
+        assert_eq!(limit.tokens, 200_000);
+        assert!(limit.reason.contains("daily limit of $3.00"));
+
+        let spent = BudgetConfig {
+            monthly_limit: 7.5,
+            ..BudgetConfig::default()
+        };
+        let limit = spent
+            .limit(&pricing, "gpt-5.4", &entries, 3, today)
+            .unwrap();
+        assert_eq!(limit.tokens, 0);
+        assert!(limit.reason.ends_with("not enough for the expected output"));
+    }
+
+    #[test]
+    fn test_current_limit_counts_this_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let ledger = LedgerConfig {
+            enabled: true,
+            path: dir.path().join("usage.jsonl").to_string_lossy().to_string(),
+        };
+        let budget = BudgetConfig {
+            daily_limit: 3.0,
+            ..BudgetConfig::default()
+        };
+        let pricing = Pricing::default();
+        let limit = |run: &[ledger::Entry]| {
+            budget
+                .current_limit(&pricing, "gpt-5.4", &ledger, run, 0)
+                .unwrap()
+                .unwrap()
+                .tokens
+        };
+        assert_eq!(limit(&[]), 1_200_000);
+
+        // $2.50 spent by an earlier request of this run, not yet in the ledger
+        let mut spent = entry(&ledger::timestamp(), 0);
+        spent.cost_usd = Some(2.5);
+        assert_eq!(limit(&[spent]), 200_000);
+    }
+
+    #[test]
+    fn test_enforce() {
+        let small = "diff --git a/a b/a\n--- a/a\n+++ b/a\n@@ -1 +1 @@\n-a\n+b\n";
+        let large = format!(
+            "diff --git a/big b/big\n--- a/big\n+++ b/big\n@@ -1 +1 @@\n{}",
+            "+more words in a line\n".repeat(50)
+        );
+        let diff = format!("{}{}", large, small);
+        let tokens = openai::count_token(&diff).unwrap();
+        let small_tokens = openai::count_token(small).unwrap();
+        let limit = Limit {
+            tokens: 100 + small_tokens,
+            reason: "budget.max_input_tokens is 100".to_string(),
+        };
+
+        let budget = BudgetConfig {
+            on_exceed: "reduce".to_string(),
+            ..BudgetConfig::default()
+        };
+        let roomy = Limit {
+            tokens: 100 + tokens,
+            ..limit.clone()
+        };
+        assert_eq!(
+            budget
+                .enforce(Some(&roomy), 100, diff.clone(), tokens)
+                .unwrap()
+                .0,
+            diff
+        );
+        let (reduced, reduced_tokens) = budget
+            .enforce(Some(&limit), 100, diff.clone(), tokens)
+            .unwrap();
+        assert_eq!(reduced, small);
+        assert_eq!(reduced_tokens, small_tokens);
+        assert!(budget
+            .enforce(Some(&limit), 200, diff.clone(), tokens)
+            .is_err());
+
+        let budget = BudgetConfig {
+            on_exceed: "abort".to_string(),
+            ..BudgetConfig::default()
+        };
+        let err = budget
+            .enforce(Some(&limit), 100, diff.clone(), tokens)
+            .unwrap_err();
+        assert!(err.to_string().contains("max_input_tokens"));
+        assert!(budget.enforce(None, 100, diff, tokens).is_ok());
+    }
+}
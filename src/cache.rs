@@ -0,0 +1,163 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::openai::CompletionResult;
+use crate::util;
+
+/// Where completions are kept so an identical request is answered without the API
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    /// How long a cached completion is reused, 0 disables the cache
+    pub ttl_minutes: u64,
+    /// Cache directory; empty means `~/.turbocommit/cache`
+    pub dir: String,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_minutes: 24 * 60,
+            dir: String::new(),
+        }
+    }
+}
+
+/// A cached completion and when it was stored, in seconds since the epoch
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    created: u64,
+    result: CompletionResult,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl CacheConfig {
+    /// Whether completions are read from and written to the cache
+    pub fn is_active(&self) -> bool {
+        self.enabled && self.ttl_minutes > 0
+    }
+
+    /// The resolved cache directory
+    pub fn dir(&self) -> PathBuf {
+        util::config_path(&self.dir, "cache")
+    }
+
+    fn is_fresh(&self, entry: &Entry, now: u64) -> bool {
+        now.saturating_sub(entry.created) < self.ttl_minutes * 60
+    }
+
+    /// Returns the cached completion for `key` unless it has expired
+    pub fn get(&self, key: &str) -> Option<CompletionResult> {
+        let content = std::fs::read_to_string(self.dir().join(format!("{}.json", key))).ok()?;
+        let mut entry: Entry = serde_json::from_str(&content).ok()?;
+        entry.result.cached = true;
+        self.is_fresh(&entry, now()).then_some(entry.result)
+    }
+
+    /// Stores a completion under `key` and removes expired entries
+    pub fn put(&self, key: &str, result: &CompletionResult) -> anyhow::Result<()> {
+        let dir = self.dir();
+        std::fs::create_dir_all(&dir)?;
+        self.prune(&dir);
+        let entry = Entry {
+            created: now(),
+            result: result.clone(),
+        };
+        std::fs::write(
+            dir.join(format!("{}.json", key)),
+            serde_json::to_string(&entry)?,
+        )?;
+        Ok(())
+    }
+
+    fn prune(&self, dir: &Path) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let now = now();
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let expired = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<Entry>(&content).ok())
+                .is_none_or(|entry| !self.is_fresh(&entry, now));
+            if expired && path.extension().is_some_and(|ext| ext == "json") {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+/// Cache key of a request: the endpoint, the number of suggestions and the
+/// serialized request with the model, parameters and messages
+pub fn key(endpoint: &str, suggestions: usize, request_json: &str) -> String {
+    util::hex(&Sha256::digest(format!(
+        "{}\n{}\n{}",
+        endpoint, suggestions, request_json
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::openai::CommitSuggestion;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn result(title: &str) -> CompletionResult {
+        CompletionResult {
+            suggestions: vec![CommitSuggestion {
+                title: title.to_string(),
+                body: None,
+            }],
+            usage: None,
+            duration: Duration::from_millis(10),
+            cached: false,
+        }
+    }
+
+    #[test]
+    fn test_key() {
+        let a = key("https://api", 3, "{\"messages\":[]}");
+        assert_eq!(a.len(), 64);
+        assert_eq!(a, key("https://api", 3, "{\"messages\":[]}"));
+        assert_ne!(a, key("https://api", 2, "{\"messages\":[]}"));
+        assert_ne!(a, key("https://other", 3, "{\"messages\":[]}"));
+    }
+
+    #[test]
+    fn test_get_and_put() {
+        let dir = tempdir().unwrap();
+        let config = CacheConfig {
+            dir: dir.path().to_string_lossy().to_string(),
+            ..CacheConfig::default()
+        };
+        assert!(config.get("abc").is_none());
+        config.put("abc", &result("feat: cached")).unwrap();
+        let cached = config.get("abc").unwrap();
+        assert_eq!(cached.suggestions[0].title, "feat: cached");
+        assert!(cached.cached);
+
+        // Expired entries are ignored and pruned on the next write
+        let expired = Entry {
+            created: now() - config.ttl_minutes * 60,
+            result: result("feat: old"),
+        };
+        let path = dir.path().join("old.json");
+        std::fs::write(&path, serde_json::to_string(&expired).unwrap()).unwrap();
+        assert!(config.get("old").is_none());
+        config.put("new", &result("feat: new")).unwrap();
+        assert!(!path.exists());
+        assert!(config.get("abc").is_some());
+    }
+}
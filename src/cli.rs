@@ -1,9 +1,370 @@
+use crate::budget::BudgetConfig;
+use crate::cache::CacheConfig;
 use crate::config::Config;
+use crate::config_command;
+use crate::convention::{self, ConventionConfig};
+use crate::debug_log::{self, DebugLogConfig};
+use crate::diff_context::DiffContext;
+use crate::jj;
+use crate::ledger::{self, LedgerConfig};
+use crate::lint::LintConfig;
 use crate::model;
-use crate::openai::count_token;
+use crate::output::{self, status};
+use crate::pricing::Pricing;
+use crate::scopes;
+use crate::template::PromptConfig;
+use crate::tickets::{self, TicketConfig};
+use crate::trailers;
+use clap::builder::PossibleValuesParser;
+use clap::{Args, Parser, Subcommand, ValueHint};
+use clap_complete::engine::{ArgValueCandidates, CompletionCandidate};
 use colored::Colorize;
+use std::path::PathBuf;
 use std::str::FromStr;
-use std::{cmp, env, process};
+
+fn banner() -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{}{}\n{}\n{}\n{}\n{}\n\n{}\n{}",
+        "    __             __".red(),
+        "   / /___  _______/ /_  ____".red(),
+        "  / __/ / / / ___/ __ \\/ __ \\".yellow(),
+        " / /_/ /_/ / /  / /_/ / /_/ /".green(),
+        " \\__/\\__,_/_/  /_.___/\\____/       ".blue(),
+        "_ __".purple(),
+        "   _________  ____ ___  ____ ___  (_) /_".purple(),
+        "  / ___/ __ \\/ __ `__ \\/ __ `__ \\/ / __/".red(),
+        " / /__/ /_/ / / / / / / / / / / / / /_".yellow(),
+        " \\___/\\____/_/ /_/ /_/_/ /_/ /_/_/\\__/".green(),
+        "NOTE: turboCommit now exclusively uses gpt-5.4"
+            .yellow()
+            .bold(),
+        "No legacy or alternate model variants are supported".bright_black()
+    )
+}
+
+const AFTER_HELP: &str = "Defaults for these options and the system message live in ~/.turbocommit.yaml, created on the first run.
+To go back to the default system message, run `turbocommit config reset-prompt`.";
+
+#[derive(Parser, Debug, Clone)]
+#[command(
+    name = "turbocommit",
+    version,
+    about,
+    before_help = banner(),
+    after_help = AFTER_HELP,
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub args: CommitArgs,
+}
+
+impl Cli {
+    /// Arguments of the commit or amend command, also given without a subcommand
+    pub fn commit_args(&self) -> &CommitArgs {
+        match &self.command {
+            Some(Command::Commit(args)) | Some(Command::Amend(args)) => args,
+            Some(Command::Pr(PrArgs { args, .. })) => args,
+            _ => &self.args,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Generate a message for the staged changes (the default)
+    Commit(CommitArgs),
+    /// Generate a new message for the last commit, without staged changes
+    Amend(CommitArgs),
+    /// Inspect and edit the config files
+    Config(ConfigArgs),
+    /// Show token usage and cost from the usage ledger
+    Stats(StatsArgs),
+    /// Generate messages from `git commit` through a prepare-commit-msg hook
+    Hook(HookArgs),
+    /// Draft a pull request title and description from the branch's changes
+    Pr(PrArgs),
+    /// Print the shell completion script, e.g. `source <(turbocommit completions bash)`
+    Completions {
+        #[arg(value_parser = PossibleValuesParser::new(SHELLS))]
+        shell: String,
+    },
+    /// Print the man page, or write one per command into a directory
+    Man {
+        #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+        dir: Option<PathBuf>,
+    },
+}
+
+/// Shells `completions` supports
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish", "powershell", "elvish"];
+
+/// Recent jj changes, completed by their short change ID
+fn revision_candidates() -> Vec<CompletionCandidate> {
+    let mut candidates = vec![CompletionCandidate::new("@").help(Some("working copy".into()))];
+    candidates.extend(
+        jj::get_jj_recent_changes(20)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(id, title)| CompletionCandidate::new(id).help(Some(title.into()))),
+    );
+    candidates
+}
+
+fn profile_candidates() -> Vec<CompletionCandidate> {
+    Config::profile_names()
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+fn config_key_candidates() -> Vec<CompletionCandidate> {
+    config_command::keys()
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct CommitArgs {
+    /// Number of choices to generate (default: 3)
+    #[arg(short = 'n', value_name = "N", value_parser = clap::value_parser!(i32).range(1..))]
+    pub choices: Option<i32>,
+    /// Model to use (must be gpt-5.4)
+    #[arg(short, long, value_name = "MODEL", value_parser = model::Model::from_str)]
+    pub model: Option<model::Model>,
+    /// Automatically generate and commit a single message
+    #[arg(short, long)]
+    pub auto_commit: bool,
+    /// Amend the last commit with the generated message
+    #[arg(long)]
+    pub amend: bool,
+    /// Check for updates and exit
+    #[arg(long)]
+    pub check_version: bool,
+    /// Set the API endpoint URL
+    #[arg(long, value_name = "URL")]
+    pub api_endpoint: Option<String>,
+    /// Load the system message from a file (a prompt template)
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub system_msg_file: Option<PathBuf>,
+    /// Disable automatic update checks
+    #[arg(long)]
+    pub disable_auto_update_check: bool,
+    /// Set the API key
+    #[arg(long, value_name = "KEY")]
+    pub api_key: Option<String>,
+    /// Reasoning effort: none, low (default), medium, high; none disables reasoning
+    #[arg(short = 'e', long, value_name = "EFFORT")]
+    pub reasoning_effort: Option<String>,
+    /// Output verbosity: low, medium (default), high
+    #[arg(short, long, value_name = "LEVEL")]
+    pub verbosity: Option<String>,
+    /// Show request/response info and token usage
+    #[arg(short, long)]
+    pub debug: bool,
+    /// Write detailed debug logs to a file (overwritten), `-` for stdout
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub debug_file: Option<String>,
+    /// Debug log format: text or jsonl (one JSON object per line)
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_parser = PossibleValuesParser::new(debug_log::FORMATS),
+        requires = "debug_file"
+    )]
+    pub debug_format: Option<String>,
+    /// Append to the debug log instead of overwriting it
+    #[arg(long, requires = "debug_file")]
+    pub debug_append: bool,
+    /// Log all message contents sent to the model
+    #[arg(long)]
+    pub debug_context: bool,
+    /// Always prompt for file selection, regardless of token count
+    #[arg(long)]
+    pub select_files: bool,
+    /// Unchanged lines shown around each change (default: 3)
+    #[arg(short = 'U', long, value_name = "N")]
+    pub context_lines: Option<u32>,
+    /// Don't name the enclosing function/class/impl in hunk headers
+    #[arg(long)]
+    pub no_symbols: bool,
+    /// Send the last N commit messages as style examples (default: 0)
+    #[arg(long, value_name = "N")]
+    pub style_examples: Option<usize>,
+    /// Infer scopes from the workspace layout (default: hint)
+    #[arg(long = "scopes", value_name = "MODE", value_parser = PossibleValuesParser::new(scopes::MODES))]
+    pub scope_inference: Option<String>,
+    /// Commit message convention (default: conventional)
+    #[arg(long, value_name = "PRESET", value_parser = PossibleValuesParser::new(convention::PRESETS))]
+    pub convention: Option<String>,
+    /// Language of the description and body, e.g. de or Japanese
+    #[arg(long, value_name = "LANG")]
+    pub language: Option<String>,
+    /// Don't check suggestions against the lint rules
+    #[arg(long)]
+    pub no_lint: bool,
+    /// Add ticket IDs from the branch name (default: off)
+    #[arg(long, value_name = "MODE", value_parser = PossibleValuesParser::new(tickets::MODES))]
+    pub tickets: Option<String>,
+    /// Append a trailer to the message (repeatable)
+    #[arg(long = "trailer", value_name = "Token: value", value_parser = parse_trailer)]
+    pub trailers: Vec<String>,
+    /// Append a Signed-off-by trailer (also enabled by git's format.signoff)
+    #[arg(short, long)]
+    pub signoff: bool,
+    /// Pick Co-authored-by trailers from recent authors
+    #[arg(long)]
+    pub co_authors: bool,
+    /// Set the config file path
+    #[arg(short, long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub config: Option<String>,
+    /// Use a config profile instead of the one matching the repository
+    #[arg(long, value_name = "NAME", add = ArgValueCandidates::new(profile_candidates))]
+    pub profile: Option<String>,
+    /// Jujutsu revision to describe (default: the working copy)
+    #[arg(short, long, value_name = "REV", add = ArgValueCandidates::new(revision_candidates))]
+    pub revision: Option<String>,
+    /// Toggle Jujutsu rewrite mode (inverts the config default)
+    #[arg(long)]
+    pub rw: bool,
+    /// Describe a unified diff from a file, or from stdin with `-`, instead of the repository
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_hint = ValueHint::FilePath,
+        conflicts_with_all = ["amend", "revision", "rw", "select_files", "co_authors"]
+    )]
+    pub diff: Option<String>,
+    /// Continue the last unfinished Commit/Edit/Revise loop of this repository
+    #[arg(
+        long,
+        conflicts_with_all = ["auto_commit", "dry_run", "format", "diff", "select_files", "co_authors", "message"]
+    )]
+    pub resume: bool,
+    /// Always request new suggestions instead of reusing a cached response
+    #[arg(long)]
+    pub no_cache: bool,
+    /// Show the messages that would be sent with token counts and cost, without sending them
+    #[arg(long, conflicts_with = "auto_commit")]
+    pub dry_run: bool,
+    /// Print the suggestions for other programs and exit without prompting
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_parser = PossibleValuesParser::new(output::FORMATS),
+        conflicts_with_all = ["auto_commit", "select_files", "co_authors"]
+    )]
+    pub format: Option<String>,
+    /// Extra explanation or instruction given to the model
+    #[arg(value_name = "MESSAGE")]
+    pub message: Vec<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct StatsArgs {
+    /// Group the totals by day, month, repository or model
+    #[arg(long, default_value = "day", value_parser = PossibleValuesParser::new(ledger::GROUPS))]
+    pub by: String,
+    /// First day to include, `YYYY-MM-DD` or `YYYY-MM`
+    #[arg(long, value_name = "DATE")]
+    pub since: Option<String>,
+    /// Last day to include, `YYYY-MM-DD` or `YYYY-MM`
+    #[arg(long, value_name = "DATE")]
+    pub until: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct PrArgs {
+    /// Branch the pull request targets [default: origin's default branch, main or master]
+    #[arg(long, value_name = "BRANCH")]
+    pub base: Option<String>,
+    #[command(flatten)]
+    pub args: CommitArgs,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct HookArgs {
+    #[command(subcommand)]
+    pub action: HookAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum HookAction {
+    /// Install the prepare-commit-msg hook in the current repository
+    Install {
+        /// Replace a prepare-commit-msg hook that wasn't installed by turbocommit
+        #[arg(long)]
+        force: bool,
+    },
+    /// Remove the hook installed by turbocommit
+    Uninstall,
+    /// Write a message into git's message file, called by the hook
+    Run {
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+        /// Where git's message comes from: message, template, merge, squash or commit
+        source: Option<String>,
+        sha: Option<String>,
+    },
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub action: ConfigAction,
+    /// Config file to use instead of ~/.turbocommit.yaml
+    #[arg(
+        id = "config_file",
+        short,
+        long = "config",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        global = true
+    )]
+    pub config: Option<PathBuf>,
+    /// Profile applied by `show`, `get` and `validate`
+    #[arg(
+        id = "config_profile",
+        long = "profile",
+        value_name = "NAME",
+        add = ArgValueCandidates::new(profile_candidates),
+        global = true
+    )]
+    pub profile: Option<String>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ConfigAction {
+    /// Print the effective config and where each value comes from
+    Show,
+    /// Print a value, e.g. `lint.header_max_length`
+    Get {
+        #[arg(add = ArgValueCandidates::new(config_key_candidates))]
+        key: String,
+    },
+    /// Set a value in your config file
+    Set {
+        #[arg(add = ArgValueCandidates::new(config_key_candidates))]
+        key: String,
+        value: String,
+    },
+    /// Check every config layer, exit with 1 on errors
+    Validate,
+    /// Print the config file locations
+    Path,
+    /// Replace system_msg with the default
+    ResetPrompt,
+}
+
+fn parse_trailer(trailer: &str) -> Result<String, String> {
+    match trailers::parse(trailer) {
+        Some(_) => Ok(trailer.to_string()),
+        None => Err("expected `Token: value`, e.g. `Reviewed-by: Name <email>`".to_string()),
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Options {
@@ -20,9 +381,37 @@ pub struct Options {
     pub verbosity: Option<String>,
     pub debug: bool,
     pub debug_file: Option<String>,
+    pub debug_log: DebugLogConfig,
     pub debug_context: bool,
     pub always_select_files: bool,
+    pub context_lines: u32,
+    pub disable_enclosing_symbols: bool,
+    pub style_examples: usize,
+    pub scope_inference: String,
+    pub convention: ConventionConfig,
+    pub language: String,
+    pub lint: LintConfig,
+    pub tickets: TicketConfig,
+    pub trailers: Vec<String>,
+    pub signoff: bool,
+    pub pick_co_authors: bool,
     pub config_file: Option<String>,
+    pub profile: Option<String>,
+    pub prompts: PromptConfig,
+    /// `json` or `plain` to print the suggestions instead of prompting
+    pub format: Option<String>,
+    /// Diff file to describe, `-` for stdin
+    pub diff: Option<String>,
+    /// Build the request without sending it
+    pub dry_run: bool,
+    /// Continue the saved session instead of starting over
+    pub resume: bool,
+    /// Git's commit message file to write a message into, from `hook run`
+    pub message_file: Option<PathBuf>,
+    pub pricing: Pricing,
+    pub cache: CacheConfig,
+    pub ledger: LedgerConfig,
+    pub budget: BudgetConfig,
     pub amend: bool,
     // Jujutsu VCS specific options
     pub jj_revision: Option<String>,
@@ -45,9 +434,32 @@ impl From<&Config> for Options {
             verbosity: Some(config.verbosity.clone()),
             debug: false,
             debug_file: None,
+            debug_log: config.debug_log.clone(),
             debug_context: false,
             always_select_files: false,
+            context_lines: config.diff_context_lines,
+            disable_enclosing_symbols: config.disable_enclosing_symbols,
+            style_examples: config.style_examples,
+            scope_inference: config.scope_inference.clone(),
+            convention: config.convention.clone(),
+            language: config.language.clone(),
+            lint: config.lint.clone(),
+            tickets: config.tickets.clone(),
+            trailers: config.trailers.clone(),
+            signoff: config.signoff,
+            pick_co_authors: false,
             config_file: None,
+            profile: None,
+            prompts: config.prompts.clone(),
+            format: None,
+            diff: None,
+            dry_run: false,
+            resume: false,
+            message_file: None,
+            pricing: config.pricing.clone(),
+            cache: config.cache.clone(),
+            ledger: config.ledger.clone(),
+            budget: config.budget.clone(),
             amend: false,
             jj_revision: None,
             jj_rewrite: config.jj_rewrite_default,
@@ -56,235 +468,138 @@ impl From<&Config> for Options {
 }
 
 impl Options {
-    pub fn new<I>(args: I, conf: &Config) -> Self
-    where
-        I: Iterator<Item = String>,
-    {
+    /// Applies the parsed command line over the config values
+    pub fn new(cli: &Cli, conf: &Config) -> anyhow::Result<Self> {
         let mut opts = Self::from(conf);
-        let mut iter = args.skip(1);
-        let mut msg = String::new();
-
-        while let Some(arg) = iter.next() {
-            match arg.as_str() {
-                "-n" => {
-                    if let Some(n) = iter.next() {
-                        opts.n = n.parse().map_or_else(
-                            |_| {
-                                println!(
-                                    "{} {}",
-                                    "Could not parse n.".red(),
-                                    "Please enter an integer.".bright_black()
-                                );
-                                process::exit(1);
-                            },
-                            |n| cmp::max(1, n),
-                        );
-                    }
-                }
-                "-m" | "--model" => {
-                    if let Some(model) = iter.next() {
-                        opts.model = match model::Model::from_str(&model) {
-                            Ok(model) => model,
-                            Err(err) => {
-                                println!(
-                                    "{} {}",
-                                    format!("Could not parse model: {}", err).red(),
-                                    "Please enter a valid model.".bright_black()
-                                );
-                                process::exit(1);
-                            }
-                        };
-                    }
-                }
-                "-a" | "--auto-commit" => {
-                    opts.auto_commmit = true;
-                    opts.n = 1;
-                }
-                "--amend" => {
-                    opts.amend = true;
-                }
-                "--check-version" => {
-                    opts.check_version_only = true;
-                }
-                "--api-endpoint" => {
-                    if let Some(endpoint) = iter.next() {
-                        opts.api_endpoint = endpoint;
-                    }
-                }
-                "--system-msg-file" => {
-                    if let Some(path) = iter.next() {
-                        match std::fs::read_to_string(&path) {
-                            Ok(content) => opts.system_msg = Some(content),
-                            Err(err) => {
-                                println!(
-                                    "{} {}",
-                                    format!("Could not read system message file: {}", err).red(),
-                                    "Please provide a valid file path.".bright_black()
-                                );
-                                process::exit(1);
-                            }
-                        }
-                    }
-                }
-                "--disable-auto-update-check" => {
-                    opts.disable_auto_update_check = true;
-                }
-                "--api-key" => {
-                    if let Some(key) = iter.next() {
-                        opts.api_key = Some(key);
-                    }
-                }
-                "-e" | "--reasoning-effort" => {
-                    if let Some(effort) = iter.next() {
-                        // Support 'none' to disable reasoning, plus low/medium/high
-                        if !["none", "low", "medium", "high"].contains(&effort.as_str()) {
-                            println!(
-                                "{} {}",
-                                "Warning: Uncommon reasoning effort value.".yellow(),
-                                "Common values are: none, low, medium, high".bright_black()
-                            );
-                        }
-                        opts.reasoning_effort = Some(effort);
-                    }
-                }
-                "-v" | "--verbosity" => {
-                    if let Some(level) = iter.next() {
-                        if !["low", "medium", "high"].contains(&level.as_str()) {
-                            println!(
-                                "{} {}",
-                                "Warning: Invalid verbosity level.".yellow(),
-                                "Valid values are: low, medium, high".bright_black()
-                            );
-                        }
-                        opts.verbosity = Some(level);
-                    }
-                }
-                "-d" | "--debug" => {
-                    opts.debug = true;
-                }
-                "--debug-file" => {
-                    if let Some(path) = iter.next() {
-                        opts.debug_file = Some(path);
-                        opts.debug = true;
-                    }
-                }
-                "--debug-context" => {
-                    opts.debug_context = true;
-                }
-                "--select-files" => {
-                    opts.always_select_files = true;
-                }
-                "-c" | "--config" => {
-                    if let Some(path) = iter.next() {
-                        opts.config_file = Some(path);
-                    }
-                }
-                "-r" | "--revision" => {
-                    if let Some(rev) = iter.next() {
-                        opts.jj_revision = Some(rev);
-                    }
-                }
-                "--rw" => {
-                    opts.jj_rewrite = !opts.jj_rewrite;
-                }
-                "-h" | "--help" => help(),
-                "--version" => {
-                    println!("turbocommit version {}", env!("CARGO_PKG_VERSION").purple());
-                    process::exit(0);
-                }
-                _ => {
-                    if arg.starts_with('-') {
-                        println!(
-                            "{} {} {}",
-                            "Unknown option: ".red(),
-                            arg.purple().bold(),
-                            "\nPlease use -h or --help for help.".bright_black()
-                        );
-                        process::exit(1);
-                    }
-                    msg.push_str(&arg);
-                    msg.push(' ');
-                }
+        let args = cli.commit_args();
+        opts.amend = matches!(cli.command, Some(Command::Amend(_)));
+        if let Some(Command::Hook(HookArgs {
+            action: HookAction::Run { file, .. },
+        })) = &cli.command
+        {
+            opts.message_file = Some(file.clone());
+            opts.n = 1;
+        }
+        // A pull request gets one description unless more are asked for
+        if matches!(cli.command, Some(Command::Pr(_))) {
+            opts.n = 1;
+        }
+
+        if let Some(n) = args.choices {
+            opts.n = n;
+        }
+        if let Some(model) = &args.model {
+            opts.model = model.clone();
+        }
+        if args.auto_commit {
+            opts.auto_commmit = true;
+            opts.n = 1;
+        }
+        opts.amend |= args.amend;
+        opts.check_version_only = args.check_version;
+        if let Some(endpoint) = &args.api_endpoint {
+            opts.api_endpoint = endpoint.clone();
+        }
+        if let Some(path) = &args.system_msg_file {
+            match std::fs::read_to_string(path) {
+                Ok(content) => opts.system_msg = Some(content),
+                Err(err) => anyhow::bail!("Could not read system message file: {}", err),
+            }
+        }
+        opts.disable_auto_update_check |= args.disable_auto_update_check;
+        opts.api_key = args.api_key.clone();
+        if let Some(effort) = &args.reasoning_effort {
+            // Support 'none' to disable reasoning, plus low/medium/high
+            if !["none", "low", "medium", "high"].contains(&effort.as_str()) {
+                status!(
+                    "{} {}",
+                    "Warning: Uncommon reasoning effort value.".yellow(),
+                    "Common values are: none, low, medium, high".bright_black()
+                );
+            }
+            opts.reasoning_effort = Some(effort.clone());
+        }
+        if let Some(level) = &args.verbosity {
+            if !["low", "medium", "high"].contains(&level.as_str()) {
+                status!(
+                    "{} {}",
+                    "Warning: Invalid verbosity level.".yellow(),
+                    "Valid values are: low, medium, high".bright_black()
+                );
+            }
+            opts.verbosity = Some(level.clone());
+        }
+        opts.debug = args.debug || args.debug_file.is_some();
+        opts.debug_file = args.debug_file.clone();
+        if let Some(format) = &args.debug_format {
+            opts.debug_log.format = format.clone();
+        }
+        opts.debug_log.append |= args.debug_append;
+        opts.debug_context = args.debug_context;
+        opts.always_select_files = args.select_files;
+        if let Some(lines) = args.context_lines {
+            opts.context_lines = lines;
+        }
+        opts.disable_enclosing_symbols |= args.no_symbols;
+        if let Some(count) = args.style_examples {
+            opts.style_examples = count;
+        }
+        if let Some(mode) = &args.scope_inference {
+            opts.scope_inference = mode.clone();
+        }
+        if let Some(preset) = &args.convention {
+            opts.convention.preset = preset.clone();
+            if let Some(message) = opts.convention.invalid_format() {
+                anyhow::bail!("Invalid custom convention: {}", message);
             }
         }
-        if !msg.is_empty() {
-            opts.msg = format!("User Explanation/Instruction: '{}'", msg.trim());
+        if let Some(language) = &args.language {
+            opts.language = language.clone();
+        }
+        if args.no_lint {
+            opts.lint.enabled = false;
+        }
+        if let Some(mode) = &args.tickets {
+            opts.tickets.mode = mode.clone();
+        }
+        opts.trailers.extend(args.trailers.iter().cloned());
+        opts.signoff |= args.signoff;
+        opts.pick_co_authors = args.co_authors;
+        opts.config_file = args.config.clone();
+        opts.profile = args.profile.clone();
+        opts.jj_revision = args.revision.clone();
+        if args.rw {
+            opts.jj_rewrite = !opts.jj_rewrite;
+        }
+        opts.format = args.format.clone();
+        opts.diff = args.diff.clone();
+        opts.dry_run = args.dry_run;
+        opts.resume = args.resume;
+        if args.no_cache {
+            opts.cache.enabled = false;
+        }
+        if opts.amend && opts.diff.is_some() {
+            anyhow::bail!("--diff can't be used to amend a commit");
         }
-        opts
+        if matches!(cli.command, Some(Command::Pr(_)))
+            && (opts.amend
+                || opts.auto_commmit
+                || opts.resume
+                || opts.diff.is_some()
+                || opts.jj_revision.is_some())
+        {
+            anyhow::bail!("pr describes the branch, it can't be combined with commit options");
+        }
+        opts.msg = args.message.join(" ").trim().to_string();
+        Ok(opts)
     }
-}
 
-fn help() {
-    println!("{}", "    __             __".red());
-    println!("{}", "   / /___  _______/ /_  ____".red());
-    println!("{}", "  / __/ / / / ___/ __ \\/ __ \\".yellow());
-    println!("{}", " / /_/ /_/ / /  / /_/ / /_/ /".green());
-    println!(
-        "{}{}",
-        " \\__/\\__,_/_/  /_.___/\\____/       ".blue(),
-        "_ __".purple()
-    );
-    println!("{}", "   _________  ____ ___  ____ ___  (_) /_".purple());
-    println!("{}", "  / ___/ __ \\/ __ `__ \\/ __ `__ \\/ / __/".red());
-    println!("{}", " / /__/ /_/ / / / / / / / / / / / / /_".yellow());
-    println!("{}", " \\___/\\____/_/ /_/ /_/_/ /_/ /_/_/\\__/".green());
-
-    println!("\nUsage: turbocommit [options] [message]\n");
-    println!(
-        "{}",
-        "NOTE: turboCommit now exclusively uses gpt-5.4"
-            .yellow()
-            .bold()
-    );
-    println!(
-        "{}\n",
-        "No legacy or alternate model variants are supported".bright_black()
-    );
-    println!("Options:");
-    println!("  -n <n>   Number of choices to generate (default: 3)\n");
-    println!("  -m <m>   Model to use (must be gpt-5.4)\n  --model <m>");
-    println!("           Example: gpt-5.4\n");
-    println!("  -a, --auto-commit  Automatically generate and commit a single message\n");
-    println!("  --amend  Amend the last commit with the generated message\n");
-    println!("  --check-version  Check for updates and exit\n");
-    println!("  --api-endpoint <url>  Set the API endpoint URL\n");
-    println!("  --system-msg-file <path>  Load system message from a file\n");
-    println!("  --disable-auto-update-check  Disable automatic update checks\n");
-    println!("  --api-key <key>  Set the API key\n");
-    println!("  -e, --reasoning-effort <effort>  Set the reasoning effort level\n");
-    println!("                              Values: none, low (default), medium, high\n");
-    println!("                              Use 'none' to disable reasoning features\n");
-    println!("  -v, --verbosity <level>  Set output verbosity level (default: medium)\n");
-    println!("                      Values: low, medium, high\n");
-    println!("  -d, --debug  Enable debug mode (shows request/response info and token usage)\n");
-    println!("  --debug-file <path>  Write detailed debug logs to specified file (overwrites existing file)\n");
-    println!("                       Use '-' to write to stdout instead of a file\n");
-    println!("  --debug-context  Log all message contents being sent to the AI\n");
-    println!("  --select-files  Always prompt for file selection, regardless of token count\n");
-    println!("  -c, --config <path>  Set the config file path\n");
-    println!("  -r, --revision <rev>  Set the Jujutsu revision to describe (default: current working directory)\n");
-    println!("  --rw  Toggle rewrite mode (inverts config default)\n");
-    println!("Anything else will be concatenated into an extra message given to the AI\n");
-    println!("You can change the defaults for these options and the system message prompt in the config file, that is created the first time running the program\n{}",
-        home::home_dir().unwrap_or_else(|| "".into()).join(".turbocommit.yaml").display());
-    println!("To go back to the default system message, delete the config file.\n");
-    println!(
-        "\nThe system message is about ~{} tokens long",
-        format!(
-            "{}",
-            count_token(
-                &crate::config::Config::load()
-                    .unwrap_or_else(|e| {
-                        println!("{}", format!("Error loading config: {}", e).red());
-                        process::exit(1);
-                    })
-                    .system_msg
-            )
-            .unwrap_or(0)
-        )
-        .green()
-    );
-    process::exit(1);
+    pub fn diff_context(&self) -> DiffContext {
+        DiffContext {
+            lines: self.context_lines,
+            symbols: !self.disable_enclosing_symbols,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -292,6 +607,11 @@ mod tests {
     use super::*;
     use crate::config::Config;
 
+    fn parse(args: &[&str], config: &Config) -> Options {
+        let cli = Cli::try_parse_from(args).unwrap();
+        Options::new(&cli, config).unwrap()
+    }
+
     #[test]
     fn test_options_from_config() {
         let config = Config::default();
@@ -319,22 +639,20 @@ mod tests {
             "test",
             "commit",
         ];
-        let args = args.into_iter().map(String::from).collect::<Vec<String>>();
-        let options = Options::new(args.into_iter(), &config);
+        let options = parse(&args, &config);
 
         assert_eq!(options.n, 3);
         assert_eq!(options.model.0, "gpt-5.4");
         assert_eq!(options.reasoning_effort, Some("medium".to_string()));
         assert_eq!(options.verbosity, Some("high".to_string()));
-        assert_eq!(options.msg, "User Explanation/Instruction: 'test commit'");
+        assert_eq!(options.msg, "test commit");
     }
 
     #[test]
     fn test_uncommon_reasoning_effort() {
         let config = Config::default();
         let args = vec!["turbocommit", "--reasoning-effort", "very-high"];
-        let args = args.into_iter().map(String::from).collect::<Vec<String>>();
-        let options = Options::new(args.into_iter(), &config);
+        let options = parse(&args, &config);
 
         assert_eq!(options.reasoning_effort, Some("very-high".to_string()));
     }
@@ -343,8 +661,7 @@ mod tests {
     fn test_debug_mode() {
         let config = Config::default();
         let args = vec!["turbocommit", "-d", "--model", "gpt-5.4"];
-        let args = args.into_iter().map(String::from).collect::<Vec<String>>();
-        let options = Options::new(args.into_iter(), &config);
+        let options = parse(&args, &config);
 
         assert!(options.debug);
         assert_eq!(options.model.0, "gpt-5.4");
@@ -356,27 +673,38 @@ mod tests {
 
         // Test debug file to a path
         let args = vec!["turbocommit", "--debug-file", "debug.log"];
-        let args = args.into_iter().map(String::from).collect::<Vec<String>>();
-        let options = Options::new(args.into_iter(), &config);
+        let options = parse(&args, &config);
 
         assert!(options.debug); // Debug mode should be enabled
         assert_eq!(options.debug_file, Some("debug.log".to_string()));
 
         // Test debug file to stdout with "-"
         let args = vec!["turbocommit", "--debug-file", "-"];
-        let args = args.into_iter().map(String::from).collect::<Vec<String>>();
-        let options = Options::new(args.into_iter(), &config);
+        let options = parse(&args, &config);
 
         assert!(options.debug);
         assert_eq!(options.debug_file, Some("-".to_string()));
 
         // Test debug mode without file
         let args = vec!["turbocommit", "-d"];
-        let args = args.into_iter().map(String::from).collect::<Vec<String>>();
-        let options = Options::new(args.into_iter(), &config);
+        let options = parse(&args, &config);
 
         assert!(options.debug);
         assert_eq!(options.debug_file, None);
+
+        // Structured, appended logs
+        let args = vec![
+            "turbocommit",
+            "--debug-file",
+            "debug.jsonl",
+            "--debug-format",
+            "jsonl",
+            "--debug-append",
+        ];
+        let options = parse(&args, &config);
+        assert_eq!(options.debug_log.format, "jsonl");
+        assert!(options.debug_log.append);
+        assert!(Cli::try_parse_from(["turbocommit", "--debug-format", "jsonl"]).is_err());
     }
 
     #[test]
@@ -389,8 +717,7 @@ mod tests {
             "--model",
             "gpt-5.4",
         ];
-        let args = args.into_iter().map(String::from).collect::<Vec<String>>();
-        let options = Options::new(args.into_iter(), &config);
+        let options = parse(&args, &config);
 
         assert_eq!(options.reasoning_effort, Some("none".to_string()));
         assert_eq!(options.model.0, "gpt-5.4");
@@ -402,14 +729,12 @@ mod tests {
 
         // Test low verbosity
         let args = vec!["turbocommit", "--verbosity", "low", "--model", "gpt-5.4"];
-        let args = args.into_iter().map(String::from).collect::<Vec<String>>();
-        let options = Options::new(args.into_iter(), &config);
+        let options = parse(&args, &config);
         assert_eq!(options.verbosity, Some("low".to_string()));
 
         // Test high verbosity
         let args = vec!["turbocommit", "--verbosity", "high", "--model", "gpt-5.4"];
-        let args = args.into_iter().map(String::from).collect::<Vec<String>>();
-        let options = Options::new(args.into_iter(), &config);
+        let options = parse(&args, &config);
         assert_eq!(options.verbosity, Some("high".to_string()));
     }
 
@@ -425,19 +750,233 @@ mod tests {
             "--model",
             "gpt-5.4",
         ];
-        let args = args.into_iter().map(String::from).collect::<Vec<String>>();
-        let options = Options::new(args.into_iter(), &config);
+        let options = parse(&args, &config);
 
         assert_eq!(options.reasoning_effort, Some("high".to_string()));
         assert_eq!(options.verbosity, Some("low".to_string()));
         assert_eq!(options.model.0, "gpt-5.4");
     }
 
+    #[test]
+    fn test_diff_context_options() {
+        let config = Config::default();
+        let options = Options::from(&config);
+        assert_eq!(options.diff_context(), DiffContext::default());
+
+        let options = parse(&["turbocommit", "-U", "8", "--no-symbols"], &config);
+        assert_eq!(
+            options.diff_context(),
+            DiffContext {
+                lines: 8,
+                symbols: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_style_examples_option() {
+        let config = Config::default();
+        let options = parse(&["turbocommit", "--style-examples", "20"], &config);
+        assert_eq!(options.style_examples, 20);
+    }
+
+    #[test]
+    fn test_scopes_option() {
+        let config = Config::default();
+        let options = parse(&["turbocommit", "--scopes", "strict"], &config);
+        assert_eq!(options.scope_inference, "strict");
+    }
+
+    #[test]
+    fn test_lint_option() {
+        let config = Config::default();
+        assert!(Options::from(&config).lint.enabled);
+        let options = parse(&["turbocommit", "--no-lint"], &config);
+        assert!(!options.lint.enabled);
+    }
+
+    #[test]
+    fn test_convention_and_language_options() {
+        let config = Config::default();
+        let options = parse(
+            &["turbocommit", "--convention", "gitmoji", "--language", "ja"],
+            &config,
+        );
+        assert_eq!(options.convention.preset, "gitmoji");
+        assert_eq!(options.language, "ja");
+    }
+
+    #[test]
+    fn test_tickets_option() {
+        let config = Config::default();
+        let options = parse(&["turbocommit", "--tickets", "trailer"], &config);
+        assert_eq!(options.tickets.mode, "trailer");
+    }
+
+    #[test]
+    fn test_trailer_options() {
+        let config = Config::default();
+        let options = parse(
+            &[
+                "turbocommit",
+                "--trailer",
+                "Reviewed-by: A <a@b.c>",
+                "-s",
+                "--co-authors",
+            ],
+            &config,
+        );
+        assert_eq!(options.trailers, vec!["Reviewed-by: A <a@b.c>".to_string()]);
+        assert!(options.signoff);
+        assert!(options.pick_co_authors);
+    }
+
     #[test]
     fn test_invalid_model_rejected() {
-        let _config = Config::default();
-        // This should fail during parsing when model validation is enforced
-        // The test expects the program to exit with an error
-        // Note: This test would need to be run in a subprocess to catch the exit
+        let err = Cli::try_parse_from(["turbocommit", "--model", "gpt-4"]).unwrap_err();
+        assert!(err.to_string().contains("Only gpt-5.4 is supported"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        // A flag missing its value is an error instead of being ignored
+        assert!(Cli::try_parse_from(["turbocommit", "-n"]).is_err());
+        assert!(Cli::try_parse_from(["turbocommit", "-n", "0"]).is_err());
+        assert!(Cli::try_parse_from(["turbocommit", "--scopes", "loose"]).is_err());
+        assert!(Cli::try_parse_from(["turbocommit", "--trailer", "no token"]).is_err());
+
+        let err = Cli::try_parse_from(["turbocommit", "--debug-contxt"]).unwrap_err();
+        assert!(err.to_string().contains("--debug-context"));
+    }
+
+    #[test]
+    fn test_format_option() {
+        let config = Config::default();
+        let options = parse(&["turbocommit", "amend", "--format", "json"], &config);
+        assert_eq!(options.format.as_deref(), Some("json"));
+        assert!(options.amend);
+
+        assert!(Cli::try_parse_from(["turbocommit", "--format", "xml"]).is_err());
+        assert!(Cli::try_parse_from(["turbocommit", "--format", "plain", "-a"]).is_err());
+    }
+
+    #[test]
+    fn test_dry_run_option() {
+        let config = Config::default();
+        let options = parse(&["turbocommit", "--dry-run", "--format", "json"], &config);
+        assert!(options.dry_run);
+        assert_eq!(options.pricing, config.pricing);
+        assert!(Cli::try_parse_from(["turbocommit", "--dry-run", "-a"]).is_err());
+    }
+
+    #[test]
+    fn test_resume_option() {
+        let config = Config::default();
+        assert!(parse(&["turbocommit", "--resume"], &config).resume);
+        assert!(Cli::try_parse_from(["turbocommit", "--resume", "--dry-run"]).is_err());
+        assert!(Cli::try_parse_from(["turbocommit", "--resume", "make it shorter"]).is_err());
+    }
+
+    #[test]
+    fn test_no_cache_option() {
+        let config = Config::default();
+        assert!(parse(&["turbocommit"], &config).cache.enabled);
+        assert!(!parse(&["turbocommit", "--no-cache"], &config).cache.enabled);
+    }
+
+    #[test]
+    fn test_diff_option() {
+        let config = Config::default();
+        let options = parse(&["turbocommit", "--diff=-", "--format", "plain"], &config);
+        assert_eq!(options.diff.as_deref(), Some("-"));
+
+        assert!(Cli::try_parse_from(["turbocommit", "--diff", "x.patch", "--amend"]).is_err());
+        let cli = Cli::try_parse_from(["turbocommit", "amend", "--diff", "x.patch"]).unwrap();
+        assert!(Options::new(&cli, &config).is_err());
+    }
+
+    #[test]
+    fn test_cli_definition() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from(["turbocommit", "completions", "zsh"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Completions { ref shell }) if shell == "zsh"));
+        assert!(Cli::try_parse_from(["turbocommit", "completions", "tcsh"]).is_err());
+    }
+
+    #[test]
+    fn test_subcommands() {
+        let config = Config::default();
+        let options = parse(&["turbocommit", "--tickets=hint", "-n=2"], &config);
+        assert_eq!(options.tickets.mode, "hint");
+        assert_eq!(options.n, 2);
+
+        let options = parse(
+            &["turbocommit", "amend", "-a", "focus", "on", "docs"],
+            &config,
+        );
+        assert!(options.amend);
+        assert!(options.auto_commmit);
+        assert_eq!(options.msg, "focus on docs");
+
+        let cli =
+            Cli::try_parse_from(["turbocommit", "config", "get", "model", "-c", "x.yaml"]).unwrap();
+        match cli.command {
+            Some(Command::Config(args)) => {
+                assert!(matches!(args.action, ConfigAction::Get { ref key } if key == "model"));
+                assert_eq!(args.config, Some(PathBuf::from("x.yaml")));
+            }
+            _ => panic!("expected the config command"),
+        }
+
+        let cli = Cli::try_parse_from(["turbocommit", "stats", "--by", "repo"]).unwrap();
+        match cli.command {
+            Some(Command::Stats(args)) => {
+                assert_eq!(args.by, "repo");
+                assert_eq!(args.since, None);
+            }
+            _ => panic!("expected the stats command"),
+        }
+        assert!(Cli::try_parse_from(["turbocommit", "stats", "--by", "week"]).is_err());
+    }
+
+    #[test]
+    fn test_hook_command() {
+        let config = Config::default();
+        let cli = Cli::try_parse_from(["turbocommit", "hook", "install", "--force"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Hook(HookArgs {
+                action: HookAction::Install { force: true }
+            }))
+        ));
+
+        let options = parse(
+            &["turbocommit", "hook", "run", ".git/COMMIT_EDITMSG", ""],
+            &config,
+        );
+        assert_eq!(
+            options.message_file,
+            Some(PathBuf::from(".git/COMMIT_EDITMSG"))
+        );
+        assert_eq!(options.n, 1);
+        assert!(Cli::try_parse_from(["turbocommit", "hook", "run"]).is_err());
+    }
+
+    #[test]
+    fn test_pr_command() {
+        let config = Config::default();
+        let cli =
+            Cli::try_parse_from(["turbocommit", "pr", "--base", "develop", "-n", "2"]).unwrap();
+        assert!(matches!(
+            &cli.command,
+            Some(Command::Pr(PrArgs { base: Some(base), .. })) if base == "develop"
+        ));
+        assert_eq!(Options::new(&cli, &config).unwrap().n, 2);
+        assert_eq!(parse(&["turbocommit", "pr"], &config).n, 1);
+
+        let cli = Cli::try_parse_from(["turbocommit", "pr", "--amend"]).unwrap();
+        assert!(Options::new(&cli, &config).is_err());
     }
 }
@@ -0,0 +1,36 @@
+use std::io::Write;
+use std::path::Path;
+
+use clap::CommandFactory;
+use clap_complete::env::Shells;
+
+use crate::cli::Cli;
+
+/// Environment variable the completion scripts set when calling back into turbocommit
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+/// Prints the script registering completions for `shell`. Values like jj
+/// revisions and profile names are completed by calling turbocommit.
+pub fn print_completions(shell: &str) -> anyhow::Result<()> {
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(shell)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported shell '{}'", shell))?;
+    let name = Cli::command().get_name().to_string();
+    let mut stdout = std::io::stdout();
+    completer.write_registration(COMPLETE_VAR, &name, &name, &name, &mut stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Prints the man page, or writes one page per command into `dir`
+pub fn print_man(dir: Option<&Path>) -> anyhow::Result<()> {
+    match dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            clap_mangen::generate_to(Cli::command(), dir)?;
+        }
+        None => clap_mangen::Man::new(Cli::command()).render(&mut std::io::stdout())?,
+    }
+    Ok(())
+}
@@ -1,7 +1,13 @@
-use crate::model;
+use crate::output::status;
+use crate::{
+    budget, budget::BudgetConfig, cache::CacheConfig, convention, convention::ConventionConfig,
+    debug_log, debug_log::DebugLogConfig, git, jj, ledger::LedgerConfig, lint::LintConfig, migrate,
+    model, pricing::Pricing, profiles, profiles::Profile, scopes, template, template::PromptConfig,
+    tickets, tickets::TicketConfig, trailers,
+};
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
-use std::{process, str::FromStr};
+use std::{collections::BTreeMap, path::Path, process, str::FromStr};
 use url::Url;
 
 #[derive(Debug)]
@@ -18,6 +24,8 @@ impl std::fmt::Display for ValidationError {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
+    #[serde(default)]
+    pub version: u64,
     #[serde(default)]
     pub model: model::Model,
     #[serde(default)]
@@ -34,13 +42,60 @@ pub struct Config {
     pub verbosity: String,
     #[serde(default)]
     pub jj_rewrite_default: bool,
+    #[serde(default = "default_diff_context_lines")]
+    pub diff_context_lines: u32,
+    #[serde(default)]
+    pub disable_enclosing_symbols: bool,
+    #[serde(default)]
+    pub style_examples: usize,
+    #[serde(default = "default_scope_inference")]
+    pub scope_inference: String,
+    #[serde(default)]
+    pub scope_map: BTreeMap<String, String>,
+    #[serde(default)]
+    pub convention: ConventionConfig,
+    #[serde(default)]
+    pub language: String,
+    #[serde(default)]
+    pub lint: LintConfig,
+    #[serde(default)]
+    pub tickets: TicketConfig,
+    #[serde(default)]
+    pub trailers: Vec<String>,
+    #[serde(default)]
+    pub signoff: bool,
+    #[serde(default)]
+    pub pricing: Pricing,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub ledger: LedgerConfig,
+    #[serde(default)]
+    pub budget: BudgetConfig,
+    #[serde(default)]
+    pub debug_log: DebugLogConfig,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    #[serde(default)]
+    pub prompts: PromptConfig,
+    #[serde(default)]
+    pub default_prompt_hash: String,
     #[serde(default)]
     pub system_msg: String,
 }
 
+fn default_diff_context_lines() -> u32 {
+    3
+}
+
+fn default_scope_inference() -> String {
+    String::from("hint")
+}
+
 impl Default for Config {
     fn default() -> Self {
-        Self {
+        let mut config = Self {
+            version: migrate::CURRENT_VERSION,
             model: model::Model("gpt-5.4".to_string()),
             api_endpoint: String::from("https://api.openai.com/v1/chat/completions"),
             api_key_env_var: String::from("OPENAI_API_KEY"),
@@ -49,8 +104,26 @@ impl Default for Config {
             reasoning_effort: String::from("low"),
             verbosity: String::from("medium"),
             jj_rewrite_default: false, // Default to overwrite mode
+            diff_context_lines: default_diff_context_lines(),
+            disable_enclosing_symbols: false,
+            style_examples: 0, // Disabled by default, each example costs tokens
+            scope_inference: default_scope_inference(),
+            scope_map: BTreeMap::new(),
+            convention: ConventionConfig::default(),
+            language: String::new(), // Empty means English
+            lint: LintConfig::default(),
+            tickets: TicketConfig::default(),
+            trailers: Vec::new(),
+            signoff: false,
+            pricing: Pricing::default(),
+            cache: CacheConfig::default(),
+            ledger: LedgerConfig::default(),
+            budget: BudgetConfig::default(),
+            debug_log: DebugLogConfig::default(),
+            profiles: BTreeMap::new(),
+            prompts: PromptConfig::default(),
             system_msg: String::from("<role>
-You generate high-quality conventional commit suggestions from repository diffs.
+You generate high-quality commit suggestions from repository diffs.
 Your job is to infer the most useful commit intent and express it clearly, precisely, and compactly.
 </role>
 
@@ -65,7 +138,7 @@ Your job is to infer the most useful commit intent and express it clearly, preci
 - The JSON must satisfy the provided structured-output schema exactly.
 - Return exactly the requested number of suggestions.
 - Each suggestion must contain:
-  - `title`: a conventional commit header
+  - `title`: a commit header following the commit rules
   - `body`: either a single concise paragraph string or `null`
 - Do not include markdown fences, explanations, bullets, or extra keys.
 </output_contract>
@@ -82,16 +155,6 @@ Optimize for semantic accuracy, user intent, and usefulness in project history.
 4. Prefer the highest-signal interpretation over a literal file-by-file summary.
 </priority_order>
 
-<commit_rules>
-- Use this title shape: `<type>(optional-scope): description`
-- Allowed types: `feat`, `fix`, `docs`, `style`, `refactor`, `test`, `build`, `ci`, `chore`
-- Add `!` only for genuine breaking changes.
-- Keep the description imperative, specific, and without a trailing period.
-- Prefer lowercase at the start unless a proper noun, acronym, or identifier requires otherwise.
-- Use a scope only when it adds meaningful precision.
-- Avoid vague descriptions like `update files`, `improve code`, or `misc changes`.
-</commit_rules>
-
 <body_rules>
 - The body is optional and should explain WHY, not restate the diff.
 - When present, write exactly one compact paragraph.
@@ -119,21 +182,142 @@ Optimize for semantic accuracy, user intent, and usefulness in project history.
 - Avoid parroting filenames, function names, or low-level edits unless they are central to intent.
 - The suggestions should be distinct but all defensible.
 </quality_bar>"),
+            default_prompt_hash: String::new(),
+        };
+        config.default_prompt_hash = migrate::prompt_hash(&config.system_msg);
+        config
+    }
+}
+
+/// Name of the config file looked up at the repository root
+pub const REPO_CONFIG_FILE: &str = ".turbocommit.yaml";
+
+/// Keys a repository config may set; anything else could let a cloned repository
+/// redirect requests (and the API key), write files elsewhere or lift spending caps
+pub const REPO_ALLOWED_KEYS: &[&str] = &[
+    "convention",
+    "lint",
+    "scope_map",
+    "tickets",
+    "trailers",
+    "language",
+];
+
+/// Whether a repository config may set the dotted config path `key`
+pub fn is_repo_key(key: &str) -> bool {
+    let top = key.split('.').next().unwrap_or_default();
+    REPO_ALLOWED_KEYS.contains(&top)
+}
+
+/// Prefix of environment variables overriding config fields; nested fields are
+/// separated by `__`, e.g. `TURBOCOMMIT_LINT__HEADER_MAX_LENGTH`
+pub const ENV_PREFIX: &str = "TURBOCOMMIT_";
+
+/// Sets the field at `path` from an environment variable value. String fields
+/// take the value verbatim, everything else is parsed as YAML.
+pub fn set_yaml_path(value: &mut serde_yaml::Value, path: &[String], raw: &str) -> bool {
+    let Some((key, rest)) = path.split_first() else {
+        return false;
+    };
+    let serde_yaml::Value::Mapping(map) = value else {
+        return false;
+    };
+    let Some(field) = map.get_mut(key.as_str()) else {
+        return false;
+    };
+    if !rest.is_empty() {
+        return set_yaml_path(field, rest, raw);
+    }
+    *field = match field {
+        serde_yaml::Value::String(_) => serde_yaml::Value::String(raw.to_string()),
+        _ => {
+            serde_yaml::from_str(raw).unwrap_or_else(|_| serde_yaml::Value::String(raw.to_string()))
+        }
+    };
+    true
+}
+
+/// Returns the value at a path of mapping keys
+pub fn get_yaml_path<'a>(
+    value: &'a serde_yaml::Value,
+    path: &[String],
+) -> Option<&'a serde_yaml::Value> {
+    path.iter()
+        .try_fold(value, |value, key| value.get(key.as_str()))
+}
+
+/// Merges `overlay` into `base`, recursing into mappings and replacing everything else
+fn merge_yaml(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    match (base, overlay) {
+        (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_yaml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
         }
+        (base, overlay) => *base = overlay,
     }
 }
 
 impl Config {
-    pub fn load_from_path(path: &std::path::Path) -> anyhow::Result<Self> {
+    /// Parses a config file, migrating it to the current version and replacing an
+    /// unmodified older default prompt. The original is kept as a backup.
+    fn parse_and_migrate(path: &Path, content: &str) -> anyhow::Result<Self> {
+        let mut value = serde_yaml::from_str::<serde_yaml::Value>(content)
+            .map_err(|err| anyhow::anyhow!("Configuration file parsing error: {}", err))?;
+        let (from, applied) = migrate::run(&mut value);
+        let refreshed = migrate::refresh_default_prompt(&mut value);
+
+        if from > migrate::CURRENT_VERSION {
+            status!(
+                "{}",
+                format!(
+                    "{} was written by a newer turbocommit (config version {}), some settings may be ignored.",
+                    path.display(),
+                    from
+                )
+                .yellow()
+            );
+        }
+        if !applied.is_empty() || refreshed {
+            let backup = path.with_file_name(format!(
+                "{}.v{}.bak",
+                path.file_name().unwrap_or_default().to_string_lossy(),
+                from
+            ));
+            std::fs::copy(path, &backup)?;
+            std::fs::write(path, serde_yaml::to_string(&value)?)?;
+            for description in applied {
+                status!(
+                    "{}",
+                    format!("Migrated config: {}", description).bright_black()
+                );
+            }
+            if refreshed {
+                status!(
+                    "{}",
+                    "Updated your system prompt to the new default.".bright_black()
+                );
+            }
+            status!(
+                "{}",
+                format!("Previous config saved to {}", backup.display()).bright_black()
+            );
+        }
+
+        serde_yaml::from_value::<Self>(value)
+            .map_err(|err| anyhow::anyhow!("Configuration file parsing error: {}", err))
+    }
+
+    pub fn load_from_path(path: &Path) -> anyhow::Result<Self> {
         //debug log the path we load from
-        println!("Loading config from path: {}", path.display());
+        status!("Loading config from path: {}", path.display());
         let config = match std::fs::read_to_string(path) {
-            Ok(config_str) => match serde_yaml::from_str::<Self>(&config_str) {
-                Ok(config) => config,
-                Err(err) => {
-                    return Err(anyhow::anyhow!("Configuration file parsing error: {}", err));
-                }
-            },
+            Ok(config_str) => Self::parse_and_migrate(path, &config_str)?,
             Err(err) => match err.kind() {
                 std::io::ErrorKind::NotFound => {
                     let msg = format!("Config file not found at: {}", path.display());
@@ -145,61 +329,30 @@ impl Config {
             },
         };
 
-        // Validate the configuration
-        if let Err(validation_errors) = config.validate() {
-            let mut error_msg = String::from("Configuration validation errors:\n");
-            for error in validation_errors {
-                error_msg.push_str(&format!("  {}\n", error));
-            }
-            error_msg.push_str(&format!(
-                "\nConfiguration file location: {}",
-                path.display()
-            ));
-
-            // If system message is empty, show the default
-            if config.system_msg.trim().is_empty() {
-                error_msg.push_str("\n\nDefault system message:\n");
-                error_msg.push_str(&Self::default().system_msg);
-            }
-
-            return Err(anyhow::anyhow!(error_msg));
-        }
-
-        // After validation passes, fill in empty system message with default
-        let mut config = config;
-        if config.system_msg.trim().is_empty() {
-            config.system_msg = Self::default().system_msg;
-        }
-
-        Ok(config)
+        config.checked(path)
     }
 
     pub fn load() -> anyhow::Result<Self> {
         let path = home::home_dir().map_or_else(
             || {
-                println!("{}", "Unable to find home directory.".red());
+                status!("{}", "Unable to find home directory.".red());
                 process::exit(1);
             },
             |path| path.join(".turbocommit.yaml"),
         );
 
         let config = match std::fs::read_to_string(&path) {
-            Ok(config_str) => match serde_yaml::from_str::<Self>(&config_str) {
-                Ok(config) => config,
-                Err(err) => {
-                    return Err(anyhow::anyhow!("Configuration file parsing error: {}", err));
-                }
-            },
+            Ok(config_str) => Self::parse_and_migrate(&path, &config_str)?,
             Err(err) => match err.kind() {
                 std::io::ErrorKind::NotFound => {
-                    println!(
+                    status!(
                         "{}",
                         "No configuration file found, creating one with default values."
                             .bright_black()
                     );
                     let default = Self::default();
                     if let Err(e) = default.save_if_changed() {
-                        println!(
+                        status!(
                             "{}",
                             format!("Warning: Failed to create default config file: {}", e)
                                 .yellow()
@@ -213,8 +366,12 @@ impl Config {
             },
         };
 
-        // Validate the configuration
-        if let Err(validation_errors) = config.validate() {
+        config.checked(&path)
+    }
+
+    /// Validates the config loaded from `path` and fills in the default system message
+    fn checked(mut self, path: &Path) -> anyhow::Result<Self> {
+        if let Err(validation_errors) = self.validate() {
             let mut error_msg = String::from("Configuration validation errors:\n");
             for error in validation_errors {
                 error_msg.push_str(&format!("  {}\n", error));
@@ -225,7 +382,7 @@ impl Config {
             ));
 
             // If system message is empty, show the default
-            if config.system_msg.trim().is_empty() {
+            if self.system_msg.trim().is_empty() {
                 error_msg.push_str("\n\nDefault system message:\n");
                 error_msg.push_str(&Self::default().system_msg);
             }
@@ -234,25 +391,229 @@ impl Config {
         }
 
         // After validation passes, fill in empty system message with default
-        let mut config = config;
-        if config.system_msg.trim().is_empty() {
-            config.system_msg = Self::default().system_msg;
+        if self.system_msg.trim().is_empty() {
+            self.system_msg = Self::default().system_msg;
         }
 
+        Ok(self)
+    }
+
+    /// The `.turbocommit.yaml` at the repository root, if there is one
+    pub fn repo_config_path() -> Option<std::path::PathBuf> {
+        let path = jj::repo_root()?.join(REPO_CONFIG_FILE);
+        // The repository may be the home directory itself
+        let is_user_config = path.canonicalize().ok() == Self::path().canonicalize().ok();
+        (path.is_file() && !is_user_config).then_some(path)
+    }
+
+    /// Deep-merges `.turbocommit.yaml` from the repository root over this config
+    pub fn with_repo_config(self) -> anyhow::Result<Self> {
+        match Self::repo_config_path() {
+            Some(path) => self.layered(&path),
+            None => Ok(self),
+        }
+    }
+
+    fn layered(self, path: &Path) -> anyhow::Result<Self> {
+        let overlay = match std::fs::read_to_string(path) {
+            Ok(config_str) => {
+                serde_yaml::from_str::<serde_yaml::Value>(&config_str).map_err(|err| {
+                    anyhow::anyhow!(
+                        "Configuration file parsing error in {}: {}",
+                        path.display(),
+                        err
+                    )
+                })?
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(self),
+            Err(err) => {
+                return Err(anyhow::anyhow!("Error reading configuration file: {}", err));
+            }
+        };
+        let mut overlay = match overlay {
+            serde_yaml::Value::Mapping(overlay) => overlay,
+            serde_yaml::Value::Null => return Ok(self),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Configuration file {} must contain a mapping",
+                    path.display()
+                ))
+            }
+        };
+
+        status!(
+            "{}",
+            format!("Using repository config: {}", path.display()).bright_black()
+        );
+        let ignored: Vec<String> = overlay
+            .keys()
+            .filter_map(|key| key.as_str().map(String::from))
+            .filter(|key| !is_repo_key(key))
+            .collect();
+        overlay.retain(|key, _| key.as_str().is_some_and(is_repo_key));
+        for key in ignored {
+            status!(
+                "{}",
+                format!(
+                    "Ignoring `{}` in the repository config, it can only be set in your own config.",
+                    key
+                )
+                .yellow()
+            );
+        }
+
+        let mut merged = serde_yaml::to_value(&self)?;
+        merge_yaml(&mut merged, serde_yaml::Value::Mapping(overlay));
+        let config = serde_yaml::from_value::<Self>(merged).map_err(|err| {
+            anyhow::anyhow!(
+                "Configuration file parsing error in {}: {}",
+                path.display(),
+                err
+            )
+        })?;
+        config.checked(path)
+    }
+
+    /// Layers the repository config, the requested (or matching) profile and the
+    /// environment over this config
+    pub fn resolve(self, profile: Option<&str>) -> anyhow::Result<Self> {
+        let config = self.with_repo_config()?;
+        let config = match profile
+            .map(String::from)
+            .or_else(|| config.matching_profile())
+        {
+            Some(profile) => {
+                status!("{}", format!("Using profile: {}", profile).bright_black());
+                config.with_profile(&profile)?
+            }
+            None => config,
+        };
+        config.with_env_overrides()
+    }
+
+    /// The first profile matching the repository root or one of its remotes
+    pub fn matching_profile(&self) -> Option<String> {
+        if self.profiles.is_empty() {
+            return None;
+        }
+        let remotes = match git::get_repo() {
+            Ok(repo) => git::remote_urls(&repo),
+            Err(_) => jj::get_jj_remote_urls(),
+        };
+        profiles::detect(&self.profiles, jj::repo_root().as_deref(), &remotes).map(String::from)
+    }
+
+    /// Applies `TURBOCOMMIT_*` environment variables over this config
+    pub fn with_env_overrides(self) -> anyhow::Result<Self> {
+        self.with_overrides(std::env::vars())
+    }
+
+    fn with_overrides(self, vars: impl Iterator<Item = (String, String)>) -> anyhow::Result<Self> {
+        let mut value = serde_yaml::to_value(&self)?;
+        let mut applied = Vec::new();
+        for (name, raw) in vars {
+            let Some(field) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let path: Vec<String> = field.split("__").map(str::to_lowercase).collect();
+            if set_yaml_path(&mut value, &path, &raw) {
+                applied.push(name);
+            } else {
+                status!(
+                    "{}",
+                    format!("Ignoring {}, it doesn't match a config field.", name).yellow()
+                );
+            }
+        }
+        if applied.is_empty() {
+            return Ok(self);
+        }
+
+        let config = serde_yaml::from_value::<Self>(value).map_err(|err| {
+            anyhow::anyhow!(
+                "Invalid value in environment ({}): {}",
+                applied.join(", "),
+                err
+            )
+        })?;
+        if let Err(validation_errors) = config.validate() {
+            let mut error_msg = format!(
+                "Environment overrides ({}) result in an invalid configuration:\n",
+                applied.join(", ")
+            );
+            for error in validation_errors {
+                error_msg.push_str(&format!("  {}\n", error));
+            }
+            return Err(anyhow::anyhow!(error_msg.trim_end().to_string()));
+        }
         Ok(config)
     }
+
+    /// Sets the field at a dotted path like `lint.header_max_length`
+    pub fn with_field(self, key: &str, raw: &str) -> anyhow::Result<Self> {
+        let mut value = serde_yaml::to_value(&self)?;
+        let path: Vec<String> = key.split('.').map(String::from).collect();
+        if !set_yaml_path(&mut value, &path, raw) {
+            return Err(anyhow::anyhow!("Unknown config field '{}'", key));
+        }
+        let config = serde_yaml::from_value::<Self>(value)
+            .map_err(|err| anyhow::anyhow!("Invalid value for {}: {}", key, err))?;
+        if let Err(validation_errors) = config.validate() {
+            let mut error_msg = String::from("Configuration validation errors:\n");
+            for error in validation_errors {
+                error_msg.push_str(&format!("  {}\n", error));
+            }
+            return Err(anyhow::anyhow!(error_msg.trim_end().to_string()));
+        }
+        Ok(config)
+    }
+
+    /// Applies the overrides of the named profile
+    pub fn with_profile(mut self, name: &str) -> anyhow::Result<Self> {
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            return Err(anyhow::anyhow!(
+                "Unknown profile '{}'. Available profiles: {}",
+                name,
+                self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            ));
+        };
+
+        if let Some(api_endpoint) = profile.api_endpoint {
+            self.api_endpoint = api_endpoint;
+        }
+        if let Some(api_key_env_var) = profile.api_key_env_var {
+            self.api_key_env_var = api_key_env_var;
+        }
+        if let Some(model) = profile.model {
+            self.model = model;
+        }
+        if let Some(reasoning_effort) = profile.reasoning_effort {
+            self.reasoning_effort = reasoning_effort;
+        }
+        if let Some(verbosity) = profile.verbosity {
+            self.verbosity = verbosity;
+        }
+
+        if let Err(validation_errors) = self.validate() {
+            let mut error_msg =
+                format!("Profile '{}' results in an invalid configuration:\n", name);
+            for error in validation_errors {
+                error_msg.push_str(&format!("  {}\n", error));
+            }
+            return Err(anyhow::anyhow!(error_msg.trim_end().to_string()));
+        }
+        Ok(self)
+    }
+
     pub fn save_if_changed(&self) -> Result<(), std::io::Error> {
-        let path = home::home_dir().map_or_else(
-            || {
-                println!("{}", "Unable to find home directory.".red());
-                process::exit(1);
-            },
-            |path| path.join(".turbocommit.yaml"),
-        );
+        self.save_to_path(&Self::path())
+    }
+
+    pub fn save_to_path(&self, path: &Path) -> Result<(), std::io::Error> {
         let config = match serde_yaml::to_string(self) {
             Ok(config) => config,
             Err(err) => {
-                println!("{}", format!("Unable to serialize config: {}", err).red());
+                status!("{}", format!("Unable to serialize config: {}", err).red());
                 return Err(std::io::Error::new(
                     std::io::ErrorKind::Other,
                     "Unable to serialize config",
@@ -260,7 +621,7 @@ impl Config {
             }
         };
 
-        if let Ok(existing_config) = std::fs::read_to_string(&path) {
+        if let Ok(existing_config) = std::fs::read_to_string(path) {
             if existing_config == config {
                 return Ok(());
             }
@@ -268,17 +629,36 @@ impl Config {
 
         std::fs::write(path, config)
     }
+    /// Names of the profiles in the user config, read without creating or
+    /// migrating the file
+    pub fn profile_names() -> Vec<String> {
+        let value: serde_yaml::Value = std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_yaml::from_str(&content).ok())
+            .unwrap_or_default();
+        value
+            .get("profiles")
+            .and_then(serde_yaml::Value::as_mapping)
+            .map(|profiles| {
+                profiles
+                    .keys()
+                    .filter_map(|name| name.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     pub fn path() -> std::path::PathBuf {
         home::home_dir().map_or_else(
             || {
-                println!("{}", "Unable to find home directory.".red());
+                status!("{}", "Unable to find home directory.".red());
                 process::exit(1);
             },
             |path| path.join(".turbocommit.yaml"),
         )
     }
 
-    fn validate(&self) -> Result<(), Vec<ValidationError>> {
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
         let mut errors = Vec::new();
         let default = Self::default();
 
@@ -303,6 +683,18 @@ impl Config {
             });
         }
 
+        // Validate profile endpoints, the rest is checked when a profile is applied
+        for (name, profile) in &self.profiles {
+            if let Some(endpoint) = &profile.api_endpoint {
+                if Url::parse(endpoint).is_err() {
+                    errors.push(ValidationError {
+                        field: format!("profiles.{}.api_endpoint", name),
+                        message: "Invalid URL format".to_string(),
+                    });
+                }
+            }
+        }
+
         // Validate number of choices
         if self.default_number_of_choices < 1 {
             errors.push(ValidationError {
@@ -314,6 +706,116 @@ impl Config {
             });
         }
 
+        // Validate scope inference mode
+        if !scopes::MODES.contains(&self.scope_inference.as_str()) {
+            errors.push(ValidationError {
+                field: "scope_inference".to_string(),
+                message: format!(
+                    "Must be one of: {} (default: {})",
+                    scopes::MODES.join(", "),
+                    default.scope_inference
+                ),
+            });
+        }
+
+        // Validate commit convention
+        if !convention::PRESETS.contains(&self.convention.preset.as_str()) {
+            errors.push(ValidationError {
+                field: "convention.preset".to_string(),
+                message: format!(
+                    "Must be one of: {} (default: {})",
+                    convention::PRESETS.join(", "),
+                    default.convention.preset
+                ),
+            });
+        }
+        if let Some(message) = self.convention.invalid_format() {
+            errors.push(ValidationError {
+                field: "convention".to_string(),
+                message,
+            });
+        }
+
+        // Validate lint rules
+        if self.lint.enabled
+            && self
+                .lint
+                .types
+                .iter()
+                .any(|t| t.is_empty() || t.contains(char::is_whitespace))
+        {
+            errors.push(ValidationError {
+                field: "lint.types".to_string(),
+                message: "Commit types must be non-empty and contain no whitespace".to_string(),
+            });
+        }
+
+        // Validate ticket extraction
+        if !tickets::MODES.contains(&self.tickets.mode.as_str()) {
+            errors.push(ValidationError {
+                field: "tickets.mode".to_string(),
+                message: format!(
+                    "Must be one of: {} (default: {})",
+                    tickets::MODES.join(", "),
+                    default.tickets.mode
+                ),
+            });
+        }
+        if let Some((pattern, err)) = self.tickets.invalid_pattern() {
+            errors.push(ValidationError {
+                field: "tickets.patterns".to_string(),
+                message: format!("Invalid regex '{}': {}", pattern, err),
+            });
+        }
+
+        // Validate trailers
+        if let Some(trailer) = self.trailers.iter().find(|t| trailers::parse(t).is_none()) {
+            errors.push(ValidationError {
+                field: "trailers".to_string(),
+                message: format!("Invalid trailer '{}', expected `Token: value`", trailer),
+            });
+        }
+
+        // Validate prices
+        if !self.pricing.is_valid() {
+            errors.push(ValidationError {
+                field: "pricing".to_string(),
+                message: "Prices must be numbers of at least 0 (USD per million tokens)"
+                    .to_string(),
+            });
+        }
+
+        // Validate budget
+        if !self.budget.is_valid() {
+            errors.push(ValidationError {
+                field: "budget".to_string(),
+                message: "Limits must be numbers of at least 0 (USD), 0 disables a limit"
+                    .to_string(),
+            });
+        }
+        if !budget::ON_EXCEED.contains(&self.budget.on_exceed.as_str()) {
+            errors.push(ValidationError {
+                field: "budget.on_exceed".to_string(),
+                message: format!(
+                    "Must be one of: {} (default: {})",
+                    budget::ON_EXCEED.join(", "),
+                    default.budget.on_exceed
+                ),
+            });
+        }
+
+        // Validate debug log
+        if !debug_log::FORMATS.contains(&self.debug_log.format.as_str()) {
+            errors.push(ValidationError {
+                field: "debug_log.format".to_string(),
+                message: format!(
+                    "Must be one of: {} (default: {})",
+                    debug_log::FORMATS.join(", "),
+                    default.debug_log.format
+                ),
+            });
+        }
+
         // Validate system message
         if self.system_msg.trim().is_empty() {
             errors.push(ValidationError {
@@ -322,6 +824,23 @@ impl Config {
             });
         }
 
+        // Validate prompt templates
+        for (field, text) in [
+            ("system_msg", &self.system_msg),
+            ("prompts.instruction", &self.prompts.instruction),
+            (
+                "prompts.current_description",
+                &self.prompts.current_description,
+            ),
+        ] {
+            if let Some(message) = template::check(text) {
+                errors.push(ValidationError {
+                    field: field.to_string(),
+                    message,
+                });
+            }
+        }
+
         if errors.is_empty() {
             Ok(())
         } else {
@@ -385,6 +904,77 @@ mod tests {
         assert_eq!(errors[0].field, "system_msg");
     }
 
+    #[test]
+    fn test_validate_invalid_scope_inference() {
+        let config = Config {
+            scope_inference: "sometimes".to_string(),
+            ..Config::default()
+        };
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "scope_inference");
+    }
+
+    #[test]
+    fn test_validate_invalid_ticket_pattern() {
+        let config = Config {
+            tickets: TicketConfig {
+                patterns: vec!["[A-Z".to_string()],
+                ..TicketConfig::default()
+            },
+            ..Config::default()
+        };
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "tickets.patterns");
+    }
+
+    #[test]
+    fn test_validate_convention() {
+        let mut config = Config {
+            convention: ConventionConfig {
+                preset: "emoji".to_string(),
+                ..ConventionConfig::default()
+            },
+            ..Config::default()
+        };
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors[0].field, "convention.preset");
+
+        config.convention.preset = "custom".to_string();
+        config.convention.title_format = "[<type>] <description>".to_string();
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors[0].field, "convention");
+
+        config.convention.types = vec!["FEATURE".to_string()];
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_prompt_templates() {
+        let mut config = Config {
+            system_msg: "Commits for {{repo_name}} on {{branch}}".to_string(),
+            ..Config::default()
+        };
+        assert!(config.validate().is_ok());
+
+        config.prompts.instruction = "Note: {{msg}}".to_string();
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "prompts.instruction");
+    }
+
+    #[test]
+    fn test_validate_invalid_trailer() {
+        let config = Config {
+            trailers: vec!["Reviewed-by: A <a@b.c>".to_string(), "oops".to_string()],
+            ..Config::default()
+        };
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "trailers");
+    }
+
     #[test]
     fn test_validate_multiple_errors() {
         let mut config = Config::default();
@@ -534,6 +1124,184 @@ system_msg: "Test message"
         assert_eq!(config.system_msg, "Test message");
     }
 
+    #[test]
+    fn test_load_from_path_partial_lint_config() {
+        let config_content = r#"
+model: gpt-5.4
+api_endpoint: https://api.openai.com/v1/chat/completions
+default_number_of_choices: 3
+system_msg: "Test message"
+lint:
+  header_max_length: 72
+  footers_required: [Refs]
+"#;
+        let (file_path, _dir) = create_test_config(config_content);
+
+        let config = Config::load_from_path(&file_path).unwrap();
+        assert_eq!(config.lint.header_max_length, Some(72));
+        assert_eq!(config.lint.footers_required, vec!["Refs".to_string()]);
+        assert!(config.lint.enabled);
+        assert_eq!(config.lint.types, LintConfig::default().types);
+    }
+
+    #[test]
+    fn test_load_from_path_migrates_legacy_config() {
+        let config_content = r#"
+model: gpt-4o
+default_temperature: 1.0
+api_endpoint: https://api.openai.com/v1/chat/completions
+default_number_of_choices: 3
+system_msg: "Test message"
+"#;
+        let (file_path, dir) = create_test_config(config_content);
+
+        let config = Config::load_from_path(&file_path).unwrap();
+        assert_eq!(config.version, migrate::CURRENT_VERSION);
+        assert_eq!(config.model.0, "gpt-5.4");
+        assert_eq!(config.default_prompt_hash, "");
+
+        let backup = dir.path().join(".turbocommit.yaml.v0.bak");
+        assert_eq!(fs::read_to_string(backup).unwrap(), config_content);
+        let migrated = fs::read_to_string(&file_path).unwrap();
+        assert!(migrated.contains("version: 2"));
+        assert!(!migrated.contains("default_temperature"));
+
+        // Current files are left alone
+        let before = fs::metadata(&file_path).unwrap().modified().unwrap();
+        Config::load_from_path(&file_path).unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), migrated);
+        assert_eq!(
+            fs::metadata(&file_path).unwrap().modified().unwrap(),
+            before
+        );
+    }
+
+    #[test]
+    fn test_repo_config_layered_over_user_config() {
+        let config_content = r#"
+api_endpoint: https://evil.example.com/v1
+default_number_of_choices: 5
+budget:
+  daily_limit: 1000
+ledger:
+  path: /tmp/elsewhere.jsonl
+scope_map:
+  docs: documentation
+lint:
+  header_max_length: 72
+"#;
+        let (file_path, _dir) = create_test_config(config_content);
+
+        let base = Config {
+            lint: LintConfig {
+                footers_required: vec!["Refs".to_string()],
+                ..LintConfig::default()
+            },
+            ..Config::default()
+        };
+        let config = base.layered(&file_path).unwrap();
+        assert_eq!(config.scope_map["docs"], "documentation");
+        // Nested settings are merged field by field
+        assert_eq!(config.lint.header_max_length, Some(72));
+        assert_eq!(config.lint.footers_required, vec!["Refs".to_string()]);
+        // Only repo-safe keys are taken, e.g. endpoints can't be redirected
+        assert_eq!(config.api_endpoint, Config::default().api_endpoint);
+        assert_eq!(config.default_number_of_choices, 3);
+        assert_eq!(config.budget, BudgetConfig::default());
+        assert_eq!(config.ledger, LedgerConfig::default());
+        assert!(is_repo_key("lint.header_max_length"));
+        assert!(!is_repo_key("prompts.dir"));
+    }
+
+    #[test]
+    fn test_repo_config_missing_or_invalid() {
+        let dir = tempdir().unwrap();
+        let config = Config::default()
+            .layered(&dir.path().join(REPO_CONFIG_FILE))
+            .unwrap();
+        assert_eq!(config.model, Config::default().model);
+
+        let (file_path, _dir) = create_test_config("tickets:\n  mode: sometimes\n");
+        let err = Config::default().layered(&file_path).unwrap_err();
+        assert!(err.to_string().contains("tickets.mode"));
+    }
+
+    #[test]
+    fn test_with_profile() {
+        let config_content = r#"
+model: gpt-5.4
+api_endpoint: https://api.openai.com/v1/chat/completions
+default_number_of_choices: 3
+system_msg: "Test message"
+profiles:
+  local-ollama:
+    api_endpoint: http://localhost:11434/v1/chat/completions
+    api_key_env_var: ""
+    reasoning_effort: none
+    match:
+      paths: ["~/oss/*"]
+"#;
+        let (file_path, _dir) = create_test_config(config_content);
+        let config = Config::load_from_path(&file_path).unwrap();
+        assert_eq!(
+            config.profiles["local-ollama"].matches.paths,
+            vec!["~/oss/*".to_string()]
+        );
+
+        let verbosity = config.verbosity.clone();
+        let config = config.with_profile("local-ollama").unwrap();
+        assert_eq!(
+            config.api_endpoint,
+            "http://localhost:11434/v1/chat/completions"
+        );
+        assert_eq!(config.api_key_env_var, "");
+        assert_eq!(config.reasoning_effort, "none");
+        assert_eq!(config.verbosity, verbosity);
+
+        let err = config.with_profile("work").unwrap_err();
+        assert!(err.to_string().contains("Available profiles: local-ollama"));
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let vars = [
+            ("TURBOCOMMIT_VERBOSITY", "high"),
+            ("TURBOCOMMIT_DEFAULT_NUMBER_OF_CHOICES", "5"),
+            ("TURBOCOMMIT_SIGNOFF", "true"),
+            ("TURBOCOMMIT_LINT__HEADER_MAX_LENGTH", "72"),
+            ("TURBOCOMMIT_TRAILERS", "[\"Reviewed-by: A <a@b.c>\"]"),
+            ("TURBOCOMMIT_SYSTEM_MSG", "Rules: none"),
+            ("TURBOCOMMIT_UNKNOWN", "x"),
+            ("HOME", "/root"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+
+        let config = Config::default().with_overrides(vars).unwrap();
+        assert_eq!(config.verbosity, "high");
+        assert_eq!(config.default_number_of_choices, 5);
+        assert!(config.signoff);
+        assert_eq!(config.lint.header_max_length, Some(72));
+        assert_eq!(config.trailers, vec!["Reviewed-by: A <a@b.c>".to_string()]);
+        assert_eq!(config.system_msg, "Rules: none");
+    }
+
+    #[test]
+    fn test_env_overrides_invalid() {
+        let vars = [("TURBOCOMMIT_DEFAULT_NUMBER_OF_CHOICES", "many")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()));
+        let err = Config::default().with_overrides(vars).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("TURBOCOMMIT_DEFAULT_NUMBER_OF_CHOICES"));
+
+        let vars = [("TURBOCOMMIT_API_ENDPOINT", "not a url")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()));
+        assert!(Config::default().with_overrides(vars).is_err());
+    }
+
     #[test]
     fn test_load_from_path_invalid_yaml() {
         let config_content = "invalid: yaml: content: [";
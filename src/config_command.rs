@@ -0,0 +1,319 @@
+use std::path::{Path, PathBuf};
+use std::process;
+
+use colored::Colorize;
+use serde_yaml::Value;
+
+use crate::cli::{ConfigAction, ConfigArgs};
+use crate::config::{self, Config};
+use crate::migrate;
+
+/// Where a config value was last set
+struct Layer {
+    source: String,
+    paths: Vec<String>,
+}
+
+/// Collects dotted paths of all leaf values, descending into non-empty mappings
+fn leaves(value: &Value, prefix: &str, out: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Mapping(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let key = key.as_str().map(String::from).unwrap_or_else(|| {
+                    serde_yaml::to_string(key)
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string()
+                });
+                let path = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                leaves(value, &path, out);
+            }
+        }
+        _ => out.push((prefix.to_string(), value.clone())),
+    }
+}
+
+fn leaf_paths(value: &Value) -> Vec<String> {
+    let mut out = Vec::new();
+    leaves(value, "", &mut out);
+    out.into_iter().map(|(path, _)| path).collect()
+}
+
+/// Dotted paths of all config fields, e.g. `lint.header_max_length`
+pub fn keys() -> Vec<String> {
+    leaf_paths(&serde_yaml::to_value(Config::default()).unwrap_or_default())
+}
+
+fn read_yaml(path: &Path) -> Value {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_yaml::from_str(&content).ok())
+        .unwrap_or(Value::Null)
+}
+
+/// Formats a value on a single line; multi-line strings are summarized
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(s) if s.contains('\n') => {
+            format!(
+                "{:?}… ({} lines)",
+                s.lines().next().unwrap_or(""),
+                s.lines().count()
+            )
+        }
+        _ => serde_json::to_string(value).unwrap_or_default(),
+    }
+}
+
+/// The source of the value at `path`: the last layer that set it or a parent of it
+fn source_of<'a>(layers: &'a [Layer], path: &str) -> &'a str {
+    layers
+        .iter()
+        .rev()
+        .find(|layer| {
+            layer
+                .paths
+                .iter()
+                .any(|p| p == path || path.starts_with(&format!("{}.", p)))
+        })
+        .map_or("default", |layer| layer.source.as_str())
+}
+
+fn fail(err: impl std::fmt::Display) -> ! {
+    println!("{}", err.to_string().red());
+    process::exit(1);
+}
+
+fn user_config(config_file: Option<&PathBuf>) -> Config {
+    let loaded = match config_file {
+        Some(path) => Config::load_from_path(path),
+        None => Config::load(),
+    };
+    loaded.unwrap_or_else(|err| fail(err))
+}
+
+fn show(config_file: Option<&PathBuf>, profile: Option<&str>) {
+    let user_path = config_file.cloned().unwrap_or_else(Config::path);
+    // The user config is written with every field, only count values that differ
+    let defaults = serde_yaml::to_value(Config::default()).unwrap_or_default();
+    let user_values = read_yaml(&user_path);
+    let mut paths = leaf_paths(&user_values);
+    paths.retain(|p| {
+        let path: Vec<String> = p.split('.').map(String::from).collect();
+        config::get_yaml_path(&user_values, &path) != config::get_yaml_path(&defaults, &path)
+    });
+    let mut layers = vec![Layer {
+        source: format!("file {}", user_path.display()),
+        paths,
+    }];
+
+    let config = user_config(config_file)
+        .with_repo_config()
+        .unwrap_or_else(|err| fail(err));
+    let mut ignored = Vec::new();
+    if let Some(path) = Config::repo_config_path() {
+        let mut values = Vec::new();
+        leaves(&read_yaml(&path), "", &mut values);
+        let (paths, rest): (Vec<_>, Vec<_>) = values
+            .into_iter()
+            .filter(|(p, _)| !p.is_empty())
+            .partition(|(p, _)| config::is_repo_key(p));
+        let paths = paths.into_iter().map(|(p, _)| p).collect();
+        ignored = rest
+            .into_iter()
+            .map(|(p, value)| (p, value, path.clone()))
+            .collect();
+        layers.push(Layer {
+            source: format!("repository {}", path.display()),
+            paths,
+        });
+    }
+
+    let config = match profile
+        .map(String::from)
+        .or_else(|| config.matching_profile())
+    {
+        Some(name) => {
+            if let Some(profile) = config.profiles.get(&name) {
+                let mut paths = leaf_paths(&serde_yaml::to_value(profile).unwrap_or_default());
+                paths.retain(|p| p != "match" && !p.starts_with("match."));
+                layers.push(Layer {
+                    source: format!("profile {}", name),
+                    paths,
+                });
+            }
+            config.with_profile(&name).unwrap_or_else(|err| fail(err))
+        }
+        None => config,
+    };
+
+    let config = config.with_env_overrides().unwrap_or_else(|err| fail(err));
+    let effective = serde_yaml::to_value(&config).unwrap_or_else(|err| fail(err));
+    for (name, _) in std::env::vars() {
+        let Some(field) = name.strip_prefix(config::ENV_PREFIX) else {
+            continue;
+        };
+        let path: Vec<String> = field.split("__").map(str::to_lowercase).collect();
+        if config::get_yaml_path(&effective, &path).is_some() {
+            layers.push(Layer {
+                source: format!("env {}", name),
+                paths: vec![path.join(".")],
+            });
+        }
+    }
+
+    let mut values = Vec::new();
+    leaves(&effective, "", &mut values);
+    for (path, value) in values {
+        println!(
+            "{}: {}  {}",
+            path.purple(),
+            format_value(&value),
+            format!("# {}", source_of(&layers, &path)).bright_black()
+        );
+    }
+    for (path, value, file) in ignored {
+        println!(
+            "{}",
+            format!(
+                "{}: {}  # ignored in repository {}, only allowed in your own config",
+                path,
+                format_value(&value),
+                file.display()
+            )
+            .bright_black()
+        );
+    }
+}
+
+fn get(config: &Config, key: &str) {
+    let value = serde_yaml::to_value(config).unwrap_or_else(|err| fail(err));
+    let path: Vec<String> = key.split('.').map(String::from).collect();
+    match config::get_yaml_path(&value, &path) {
+        Some(Value::String(s)) => println!("{}", s),
+        Some(value) => print!("{}", serde_yaml::to_string(value).unwrap_or_default()),
+        None => fail(format!("Unknown config field '{}'", key)),
+    }
+}
+
+fn set(config_file: Option<&PathBuf>, key: &str, value: &str) {
+    let path = config_file.cloned().unwrap_or_else(Config::path);
+    let config = user_config(config_file)
+        .with_field(key, value)
+        .unwrap_or_else(|err| fail(err));
+    if let Err(err) = config.save_to_path(&path) {
+        fail(format!("Unable to write {}: {}", path.display(), err));
+    }
+    println!(
+        "{}",
+        format!("Set {} in {}", key, path.display()).bright_black()
+    );
+}
+
+fn reset_prompt(config_file: Option<&PathBuf>) {
+    let path = config_file.cloned().unwrap_or_else(Config::path);
+    // Edit the raw file so this also works when the current prompt is invalid
+    let mut value = read_yaml(&path);
+    let Value::Mapping(map) = &mut value else {
+        fail(format!(
+            "{} doesn't contain a config mapping",
+            path.display()
+        ));
+    };
+    map.insert(
+        Value::String("system_msg".to_string()),
+        Value::String(Config::default().system_msg),
+    );
+    map.insert(
+        Value::String("default_prompt_hash".to_string()),
+        Value::String(migrate::default_prompt_hash()),
+    );
+    let content = serde_yaml::to_string(&value).unwrap_or_else(|err| fail(err));
+    if let Err(err) = std::fs::write(&path, content) {
+        fail(format!("Unable to write {}: {}", path.display(), err));
+    }
+    println!(
+        "{}",
+        format!("Reset system_msg to the default in {}", path.display()).green()
+    );
+}
+
+/// Runs `turbocommit config <command>` and exits
+pub fn run(args: &ConfigArgs) -> ! {
+    let config_file = args.config.as_ref();
+    let profile = args.profile.as_deref();
+
+    match &args.action {
+        ConfigAction::Show => show(config_file, profile),
+        ConfigAction::Get { key } => {
+            let config = user_config(config_file)
+                .resolve(profile)
+                .unwrap_or_else(|err| fail(err));
+            get(&config, key);
+        }
+        ConfigAction::Set { key, value } => set(config_file, key, value),
+        ConfigAction::Validate => {
+            user_config(config_file)
+                .resolve(profile)
+                .unwrap_or_else(|err| fail(err));
+            println!("{}", "Configuration is valid.".green());
+        }
+        ConfigAction::Path => {
+            println!(
+                "{}",
+                config_file.cloned().unwrap_or_else(Config::path).display()
+            );
+            if let Some(path) = Config::repo_config_path() {
+                println!("{}", path.display());
+            }
+        }
+        ConfigAction::ResetPrompt => reset_prompt(config_file),
+    }
+    process::exit(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leaves_and_sources() {
+        let value: Value = serde_yaml::from_str(
+            "model: gpt-5.4\nlint:\n  enabled: true\n  types: []\nscope_map: {}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            leaf_paths(&value),
+            vec!["model", "lint.enabled", "lint.types", "scope_map"]
+        );
+
+        let layers = vec![
+            Layer {
+                source: "file".to_string(),
+                paths: vec!["model".to_string(), "lint.enabled".to_string()],
+            },
+            Layer {
+                source: "env".to_string(),
+                paths: vec!["lint".to_string()],
+            },
+        ];
+        assert_eq!(source_of(&layers, "model"), "file");
+        assert_eq!(source_of(&layers, "lint.enabled"), "env");
+        assert_eq!(source_of(&layers, "scope_map"), "default");
+    }
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(&Value::String("a".to_string())), "\"a\"");
+        assert_eq!(
+            format_value(&Value::String("first\nsecond".to_string())),
+            "\"first\"… (2 lines)"
+        );
+        let list: Value = serde_yaml::from_str("[feat, fix]").unwrap();
+        assert_eq!(format_value(&list), "[\"feat\",\"fix\"]");
+    }
+}
@@ -0,0 +1,371 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::lint::{parse_header, Header};
+
+/// Commit convention presets accepted in the config and on the command line
+pub const PRESETS: &[&str] = &["conventional", "gitmoji", "angular", "kernel", "custom"];
+
+const CONVENTIONAL_TYPES: &[&str] = &[
+    "feat", "fix", "docs", "style", "refactor", "test", "build", "ci", "chore",
+];
+
+const ANGULAR_TYPES: &[&str] = &[
+    "build", "ci", "docs", "feat", "fix", "perf", "refactor", "test",
+];
+
+const GITMOJIS: &[(&str, &str)] = &[
+    ("✨", "introduce new features"),
+    ("🐛", "fix a bug"),
+    ("🚑️", "critical hotfix"),
+    ("🩹", "simple fix for a non-critical issue"),
+    ("🔒️", "fix security or privacy issues"),
+    ("📝", "add or update documentation"),
+    ("🎨", "improve structure or format of the code"),
+    ("♻️", "refactor code"),
+    ("⚡️", "improve performance"),
+    ("🔥", "remove code or files"),
+    ("✅", "add, update or pass tests"),
+    ("👷", "add or update the CI build system"),
+    ("📦️", "add or update build scripts or compiled files"),
+    ("⬆️", "upgrade dependencies"),
+    ("⬇️", "downgrade dependencies"),
+    ("🔧", "add or update configuration files"),
+    ("🌐", "internationalization and localization"),
+    ("💥", "introduce breaking changes"),
+    ("🗑️", "deprecate code that needs to be cleaned up"),
+];
+
+/// The commit message convention suggestions follow
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ConventionConfig {
+    /// `conventional`, `gitmoji`, `angular`, `kernel` or `custom`
+    pub preset: String,
+    /// Title template of the `custom` preset using `<type>`, `<scope>` and `<description>`
+    pub title_format: String,
+    /// Allowed types of the `custom` preset
+    pub types: Vec<String>,
+}
+
+impl Default for ConventionConfig {
+    fn default() -> Self {
+        Self {
+            preset: String::from("conventional"),
+            title_format: String::from("<type>: <description>"),
+            types: Vec::new(),
+        }
+    }
+}
+
+/// Gitmoji may or may not carry the emoji variation selector
+pub fn same_kind(a: &str, b: &str) -> bool {
+    a.trim_end_matches('\u{fe0f}') == b.trim_end_matches('\u{fe0f}')
+}
+
+impl ConventionConfig {
+    /// Types allowed by the preset; empty means any type (or subsystem)
+    pub fn types(&self) -> Vec<String> {
+        match self.preset.as_str() {
+            "gitmoji" => GITMOJIS
+                .iter()
+                .map(|(emoji, _)| emoji.to_string())
+                .collect(),
+            "angular" => ANGULAR_TYPES.iter().map(|t| t.to_string()).collect(),
+            "kernel" => Vec::new(),
+            "custom" => self.types.clone(),
+            _ => CONVENTIONAL_TYPES.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    /// The header length limit of the preset, unless `lint.header_max_length` is set
+    pub fn header_max_length(&self) -> usize {
+        match self.preset.as_str() {
+            "kernel" => 75,
+            _ => 100,
+        }
+    }
+
+    /// The title shape shown to the model and in lint messages
+    pub fn shape(&self) -> String {
+        match self.preset.as_str() {
+            "gitmoji" => String::from("<gitmoji> description"),
+            "kernel" => String::from("<subsystem>: summary"),
+            "custom" => self.title_format.clone(),
+            _ => String::from("<type>(optional-scope): description"),
+        }
+    }
+
+    /// Schema description of the `title` field
+    pub fn title_description(&self) -> String {
+        let name = match self.preset.as_str() {
+            "gitmoji" => "Gitmoji commit title",
+            "angular" => "Angular commit title",
+            "kernel" => "Linux kernel style commit title",
+            "custom" => "Commit title",
+            _ => "Conventional commit title",
+        };
+        format!("{} ({})", name, self.shape())
+    }
+
+    /// Returns why the custom title format is unusable, if it is
+    pub fn invalid_format(&self) -> Option<String> {
+        if self.preset != "custom" {
+            return None;
+        }
+        if !self.title_format.contains("<description>") {
+            return Some(String::from("title_format must contain `<description>`"));
+        }
+        if self.title_format.contains("<type>") && self.types.is_empty() {
+            return Some(String::from(
+                "types must not be empty when title_format contains `<type>`",
+            ));
+        }
+        None
+    }
+
+    fn custom_regex(&self) -> Option<Regex> {
+        let mut pattern = String::from("^");
+        let mut rest = self.title_format.as_str();
+        while let Some(start) = rest.find('<') {
+            let Some(len) = rest[start..].find('>') else {
+                break;
+            };
+            let group = match &rest[start..=start + len] {
+                "<type>" => r"(?P<type>\S+?)",
+                "<scope>" => r"(?P<scope>[^\s()\[\]]+)",
+                "<description>" => r"(?P<description>.+?)",
+                _ => {
+                    pattern.push_str(&regex::escape(&rest[..=start + len]));
+                    rest = &rest[start + len + 1..];
+                    continue;
+                }
+            };
+            pattern.push_str(&regex::escape(&rest[..start]));
+            pattern.push_str(group);
+            rest = &rest[start + len + 1..];
+        }
+        pattern.push_str(&regex::escape(rest));
+        pattern.push('$');
+        Regex::new(&pattern).ok()
+    }
+
+    /// Splits a title into its parts according to the preset
+    pub fn parse_header<'a>(&self, title: &'a str) -> Option<Header<'a>> {
+        match self.preset.as_str() {
+            "gitmoji" => {
+                let (emoji, subject) = title.split_once(' ')?;
+                if emoji.is_empty() || emoji.is_ascii() && !emoji.starts_with(':') {
+                    return None;
+                }
+                Some(Header {
+                    kind: emoji,
+                    scope: None,
+                    breaking: false,
+                    subject: subject.trim(),
+                    subject_start: title.len() - subject.trim_start().len(),
+                })
+            }
+            "kernel" => {
+                let (subsystem, subject) = title.split_once(": ")?;
+                if subsystem.is_empty() || subsystem.contains(char::is_whitespace) {
+                    return None;
+                }
+                Some(Header {
+                    kind: subsystem,
+                    scope: None,
+                    breaking: false,
+                    subject: subject.trim(),
+                    subject_start: title.len() - subject.trim_start().len(),
+                })
+            }
+            "custom" => {
+                let captures = self.custom_regex()?.captures(title)?;
+                let description = captures.name("description")?;
+                let subject = description.as_str();
+                Some(Header {
+                    kind: captures.name("type").map_or("", |m| m.as_str()),
+                    scope: captures.name("scope").map(|m| m.as_str()),
+                    breaking: false,
+                    subject: subject.trim(),
+                    subject_start: description.start() + subject.len() - subject.trim_start().len(),
+                })
+            }
+            _ => parse_header(title),
+        }
+    }
+
+    /// The `<commit_rules>` section of the system prompt
+    pub fn rules(&self) -> String {
+        let rules = match self.preset.as_str() {
+            "gitmoji" => {
+                let list: Vec<String> = GITMOJIS
+                    .iter()
+                    .map(|(emoji, meaning)| format!("  - {} {}", emoji, meaning))
+                    .collect();
+                format!(
+                    "- Use this title shape: `<gitmoji> description`, starting with exactly one emoji character (not a `:code:`).
+- Allowed gitmojis:
+{}
+- Keep the description imperative, specific, and without a trailing period.
+- Start the description with a capital letter.
+- Avoid vague descriptions like `update files`, `improve code`, or `misc changes`.",
+                    list.join("\n")
+                )
+            }
+            "kernel" => String::from(
+                "- Use this title shape: `<subsystem>: summary`, e.g. `net: ipv4: fix checksum offload`.
+- The subsystem is the affected area or path prefix, without spaces.
+- Keep the summary imperative, specific, and without a trailing period.
+- Keep the title under 75 characters.
+- Avoid vague summaries like `update files`, `improve code`, or `misc changes`.",
+            ),
+            "custom" => {
+                let mut rules = format!("- Use this title shape: `{}`", self.title_format);
+                if !self.types.is_empty() {
+                    let types: Vec<String> = self.types.iter().map(|t| format!("`{}`", t)).collect();
+                    rules.push_str(&format!("\n- Allowed types: {}", types.join(", ")));
+                }
+                rules.push_str("\n- Keep the description imperative, specific, and without a trailing period.
+- Avoid vague descriptions like `update files`, `improve code`, or `misc changes`.");
+                rules
+            }
+            preset => {
+                let types: Vec<String> = self.types().iter().map(|t| format!("`{}`", t)).collect();
+                let (scope, case) = if preset == "angular" {
+                    (
+                        "- Use a scope naming the affected package or area whenever one applies.",
+                        "- Start the description with a lowercase letter.",
+                    )
+                } else {
+                    (
+                        "- Use a scope only when it adds meaningful precision.",
+                        "- Prefer lowercase at the start unless a proper noun, acronym, or identifier requires otherwise.",
+                    )
+                };
+                format!(
+                    "- Use this title shape: `<type>(optional-scope): description`
+- Allowed types: {}
+- Add `!` only for genuine breaking changes.
+- Keep the description imperative, specific, and without a trailing period.
+{}
+{}
+- Avoid vague descriptions like `update files`, `improve code`, or `misc changes`.",
+                    types.join(", "),
+                    case,
+                    scope
+                )
+            }
+        };
+        format!("<commit_rules>\n{}\n</commit_rules>", rules)
+    }
+
+    /// Puts the preset's rules into the system prompt's `<commit_rules>` slot,
+    /// replacing rules a saved or custom prompt carries so they can't contradict
+    /// the schema and linter, or appends them when there is no slot
+    pub fn with_rules(&self, system_msg: &str) -> String {
+        const OPEN: &str = "<commit_rules>";
+        const CLOSE: &str = "</commit_rules>";
+        let mut rest = system_msg;
+        let mut prompt = String::new();
+        let mut slot = None;
+        while let Some(start) = rest.find(OPEN) {
+            let Some(len) = rest[start..].find(CLOSE) else {
+                break;
+            };
+            prompt.push_str(&rest[..start]);
+            slot.get_or_insert(prompt.len());
+            rest = &rest[start + len + CLOSE.len()..];
+        }
+        prompt.push_str(rest);
+        match slot {
+            Some(at) => prompt.insert_str(at, &self.rules()),
+            None => prompt = format!("{}\n\n{}", prompt, self.rules()),
+        }
+        prompt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(name: &str) -> ConventionConfig {
+        ConventionConfig {
+            preset: name.to_string(),
+            ..ConventionConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_header_per_preset() {
+        let header = preset("gitmoji").parse_header("✨ Add endpoint").unwrap();
+        assert_eq!((header.kind, header.subject), ("✨", "Add endpoint"));
+        assert!(preset("gitmoji")
+            .parse_header("feat: add endpoint")
+            .is_none());
+
+        let header = preset("kernel")
+            .parse_header("net/ipv4: fix checksum")
+            .unwrap();
+        assert_eq!((header.kind, header.subject), ("net/ipv4", "fix checksum"));
+        assert!(preset("kernel").parse_header("Fix the thing").is_none());
+
+        assert_eq!(
+            preset("angular").parse_header("perf(core): cache"),
+            parse_header("perf(core): cache")
+        );
+    }
+
+    #[test]
+    fn test_custom_format() {
+        let convention = ConventionConfig {
+            preset: "custom".to_string(),
+            title_format: "[<type>] <scope>: <description>".to_string(),
+            types: vec!["FEATURE".to_string(), "BUGFIX".to_string()],
+        };
+        assert_eq!(convention.invalid_format(), None);
+        let header = convention
+            .parse_header("[BUGFIX] api: handle nulls")
+            .unwrap();
+        assert_eq!(header.kind, "BUGFIX");
+        assert_eq!(header.scope, Some("api"));
+        assert_eq!(header.subject, "handle nulls");
+        assert!(convention.parse_header("fix: handle nulls").is_none());
+        assert!(convention
+            .rules()
+            .contains("Allowed types: `FEATURE`, `BUGFIX`"));
+
+        let convention = ConventionConfig {
+            types: Vec::new(),
+            ..convention
+        };
+        assert!(convention.invalid_format().is_some());
+    }
+
+    #[test]
+    fn test_with_rules() {
+        let saved = "<role>\nx\n</role>\n\n<commit_rules>\n- Allowed types: `feat`\n</commit_rules>\n\n<body_rules>\ny\n</body_rules>";
+        let prompt = preset("gitmoji").with_rules(saved);
+        assert_eq!(prompt.matches("<commit_rules>").count(), 1);
+        assert!(!prompt.contains("Allowed types: `feat`"));
+        assert!(prompt.contains("Allowed gitmojis"));
+        assert!(prompt.starts_with("<role>\nx\n</role>\n\n<commit_rules>"));
+        assert!(prompt.ends_with("</commit_rules>\n\n<body_rules>\ny\n</body_rules>"));
+
+        let prompt = preset("kernel").with_rules("Be brief.");
+        assert!(prompt.starts_with("Be brief.\n\n<commit_rules>"));
+    }
+
+    #[test]
+    fn test_types_and_descriptions() {
+        assert!(preset("angular").types().contains(&"perf".to_string()));
+        assert!(!preset("conventional").types().contains(&"perf".to_string()));
+        assert!(preset("kernel").types().is_empty());
+        assert!(same_kind("♻️", "♻"));
+        assert_eq!(
+            preset("conventional").title_description(),
+            "Conventional commit title (<type>(optional-scope): description)"
+        );
+    }
+}
@@ -1,38 +1,254 @@
 use std::fs::{File, OpenOptions};
 use std::io::{self, Write};
-use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::{patch, util};
+
+/// Token shapes replaced in every log line, whole words only so text like
+/// `disk-usage` is left alone
+static REDACTIONS: LazyLock<[(Regex, &str); 2]> = LazyLock::new(|| {
+    [
+        (
+            Regex::new(r"\bsk-[A-Za-z0-9_\-]{16,}").unwrap(),
+            "sk-[REDACTED]",
+        ),
+        (
+            Regex::new(r"(?i)\bbearer\s+[A-Za-z0-9._\-]{8,}").unwrap(),
+            "Bearer [REDACTED]",
+        ),
+    ]
+});
+
+/// Formats `debug_log.format` and `--debug-format` accept
+pub const FORMATS: &[&str] = &["text", "jsonl"];
+
+/// How `--debug-file` logs are written
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct DebugLogConfig {
+    /// `text` (`timestamp;category;content`) or `jsonl` (one JSON object per line)
+    pub format: String,
+    /// Append to the log instead of overwriting it on every run
+    pub append: bool,
+    /// Rotate the log when it grows beyond this size, 0 disables rotation
+    pub max_size_mb: u64,
+    /// Rotated logs kept as `<file>.1` (newest) to `<file>.<max_files>`
+    pub max_files: usize,
+    /// Replace diffs in logged requests with their size
+    pub redact_diff: bool,
+}
+
+impl Default for DebugLogConfig {
+    fn default() -> Self {
+        Self {
+            format: String::from("text"),
+            append: false,
+            max_size_mb: 10,
+            max_files: 3,
+            redact_diff: false,
+        }
+    }
+}
+
+/// One line of the `jsonl` format
+#[derive(Serialize)]
+struct Record<'a> {
+    timestamp: String,
+    session_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<u64>,
+    level: &'static str,
+    category: &'a str,
+    elapsed_ms: u128,
+    /// Content that is JSON itself, e.g. requests and responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
 
 pub struct DebugLogger {
     file: Option<File>,
+    path: Option<PathBuf>,
+    /// Bytes in the current log file, for rotation
+    size: u64,
     use_stdout: bool,
+    config: DebugLogConfig,
+    /// Identifies the lines of one run when logs are appended
+    session_id: String,
+    /// Number of the current request within the session
+    request_id: Option<u64>,
+    started: Instant,
+    secrets: Vec<String>,
+}
+
+/// Moves `<file>` to `<file>.1`, `<file>.1` to `<file>.2` and so on, dropping the oldest
+fn rotate(path: &Path, max_files: usize) {
+    let rotated = |n: usize| PathBuf::from(format!("{}.{}", path.display(), n));
+    if max_files == 0 {
+        let _ = std::fs::remove_file(path);
+        return;
+    }
+    let _ = std::fs::remove_file(rotated(max_files));
+    for n in (1..max_files).rev() {
+        let _ = std::fs::rename(rotated(n), rotated(n + 1));
+    }
+    let _ = std::fs::rename(path, rotated(1));
+}
+
+fn new_session_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let digest = Sha256::digest(format!("{}-{}", nanos, std::process::id()));
+    util::hex(&digest[..8])
 }
 
 impl DebugLogger {
-    pub fn new(debug_file: Option<String>) -> Self {
-        let (file, use_stdout) = match debug_file {
-            Some(path) if path == "-" => (None, true),
-            Some(path) => (
-                OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .truncate(true)
-                    .open(Path::new(&path))
-                    .ok(),
-                false,
-            ),
-            None => (None, false),
+    pub fn new(debug_file: Option<String>, config: &DebugLogConfig) -> Self {
+        let mut logger = Self {
+            file: None,
+            path: None,
+            size: 0,
+            use_stdout: false,
+            config: config.clone(),
+            session_id: new_session_id(),
+            request_id: None,
+            started: Instant::now(),
+            secrets: Vec::new(),
         };
-        Self { file, use_stdout }
+        match debug_file {
+            Some(path) if path == "-" => logger.use_stdout = true,
+            Some(path) => {
+                let path = PathBuf::from(path);
+                if config.append
+                    && logger.over_size(std::fs::metadata(&path).map_or(0, |m| m.len()))
+                {
+                    rotate(&path, config.max_files);
+                }
+                logger.path = Some(path);
+                logger.open(config.append);
+            }
+            None => {}
+        }
+        logger
     }
 
-    pub fn log(&mut self, category: &str, content: &str) {
-        let timestamp = SystemTime::now()
+    fn over_size(&self, size: u64) -> bool {
+        self.config.max_size_mb > 0 && size >= self.config.max_size_mb * 1024 * 1024
+    }
+
+    fn open(&mut self, append: bool) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)
+            .ok();
+        self.size = match append {
+            true => std::fs::metadata(path).map_or(0, |m| m.len()),
+            false => 0,
+        };
+    }
+
+    /// Hides a secret like the API key wherever it appears in the log
+    pub fn with_secret(mut self, secret: &str) -> Self {
+        if secret.len() >= 8 {
+            self.secrets.push(secret.to_string());
+        }
+        self
+    }
+
+    /// Starts a new request; the following lines carry its ID
+    pub fn begin_request(&mut self) {
+        self.request_id = Some(self.request_id.map_or(1, |id| id + 1));
+    }
+
+    fn redact(&self, content: &str) -> String {
+        let mut content = content.to_string();
+        for secret in &self.secrets {
+            content = content.replace(secret.as_str(), "[REDACTED]");
+        }
+        for (re, replacement) in REDACTIONS.iter() {
+            content = re.replace_all(&content, *replacement).into_owned();
+        }
+        content
+    }
+
+    /// Replaces diff messages of a request with their size
+    fn redact_diff(request: &mut Value) {
+        let Some(messages) = request.get_mut("messages").and_then(Value::as_array_mut) else {
+            return;
+        };
+        for content in messages.iter_mut().filter_map(|m| m.get_mut("content")) {
+            if let Some(text) = content.as_str() {
+                if !patch::files(text).is_empty() {
+                    *content =
+                        Value::String(format!("[diff redacted: {} lines]", text.lines().count()));
+                }
+            }
+        }
+    }
+
+    fn format_line(&self, category: &str, content: &str) -> String {
+        let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis();
+            .unwrap_or_default();
+        let mut data = serde_json::from_str::<Value>(content)
+            .ok()
+            .filter(|value| value.is_object() || value.is_array());
+        if self.config.redact_diff && category == "request" {
+            if let Some(request) = &mut data {
+                Self::redact_diff(request);
+            }
+        }
+
+        if self.config.format != "jsonl" {
+            let content = match (&data, self.config.redact_diff && category == "request") {
+                (Some(data), true) => data.to_string(),
+                _ => content.to_string(),
+            };
+            return format!("{};{};{}\n", now.as_millis(), category, content);
+        }
+
+        let timestamp = chrono::DateTime::<chrono::Local>::from(UNIX_EPOCH + now)
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, false);
+        let record = Record {
+            timestamp,
+            session_id: &self.session_id,
+            request_id: self.request_id,
+            level: match category {
+                "error" => "error",
+                "info" => "info",
+                _ => "debug",
+            },
+            category,
+            elapsed_ms: self.started.elapsed().as_millis(),
+            message: data.is_none().then(|| content.to_string()),
+            data,
+        };
+        let mut line = serde_json::to_string(&record).unwrap_or_default();
+        line.push('\n');
+        line
+    }
 
-        let log_line = format!("{};{};{}\n", timestamp, category, content);
+    pub fn log(&mut self, category: &str, content: &str) {
+        if !self.use_stdout && self.file.is_none() {
+            return;
+        }
+        let log_line = self.redact(&self.format_line(category, content));
 
         if self.use_stdout {
             let _ = io::stdout().write_all(log_line.as_bytes());
@@ -40,6 +256,14 @@ impl DebugLogger {
         } else if let Some(file) = &mut self.file {
             let _ = file.write_all(log_line.as_bytes());
             let _ = file.flush();
+            self.size += log_line.len() as u64;
+            if self.over_size(self.size) {
+                self.file = None;
+                if let Some(path) = &self.path {
+                    rotate(path, self.config.max_files);
+                }
+                self.open(false);
+            }
         }
     }
 
@@ -64,29 +288,116 @@ impl DebugLogger {
 mod tests {
     use super::*;
     use std::io::Read;
-    use tempfile::NamedTempFile;
+    use tempfile::{tempdir, NamedTempFile};
+
+    fn read(path: &Path) -> String {
+        let mut content = String::new();
+        File::open(path)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
 
     #[test]
     fn test_debug_logger_file() {
         let temp_file = NamedTempFile::new().unwrap();
         let path = temp_file.path().to_str().unwrap().to_string();
 
-        let mut logger = DebugLogger::new(Some(path.clone()));
+        let mut logger = DebugLogger::new(Some(path.clone()), &DebugLogConfig::default());
         logger.log_info("test message");
 
-        let mut content = String::new();
-        File::open(path)
-            .unwrap()
-            .read_to_string(&mut content)
-            .unwrap();
-
+        let content = read(Path::new(&path));
         assert!(content.contains("test message"));
         assert!(content.contains(";info;"));
     }
 
     #[test]
     fn test_debug_logger_none() {
-        let mut logger = DebugLogger::new(None);
+        let mut logger = DebugLogger::new(None, &DebugLogConfig::default());
         logger.log_info("test message"); // Should not panic
     }
+
+    #[test]
+    fn test_jsonl_format_and_redaction() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_str().unwrap().to_string();
+        let config = DebugLogConfig {
+            format: "jsonl".to_string(),
+            redact_diff: true,
+            ..DebugLogConfig::default()
+        };
+
+        let mut logger = DebugLogger::new(Some(path.clone()), &config).with_secret("my-secret-key");
+        logger.log_info("before any request");
+        logger.begin_request();
+        logger.log_request(
+            r#"{"model":"gpt-5.4","messages":[{"role":"system","content":"rules"},{"role":"user","content":"--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n+b"}]}"#,
+        );
+        logger.log_error("failed with my-secret-key and sk-abcdefghijklmnopqrstuv\nsecond line");
+
+        let lines: Vec<Value> = read(Path::new(&path))
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["level"], "info");
+        assert!(lines[0].get("request_id").is_none());
+        assert_eq!(lines[1]["request_id"], 1);
+        assert_eq!(lines[1]["session_id"], lines[0]["session_id"]);
+        assert_eq!(lines[1]["data"]["messages"][0]["content"], "rules");
+        assert_eq!(
+            lines[1]["data"]["messages"][1]["content"],
+            "[diff redacted: 5 lines]"
+        );
+        assert_eq!(
+            lines[2]["message"],
+            "failed with [REDACTED] and sk-[REDACTED]\nsecond line"
+        );
+        assert_eq!(lines[2]["level"], "error");
+    }
+
+    #[test]
+    fn test_redact_whole_tokens_only() {
+        let logger = DebugLogger::new(None, &DebugLogConfig::default());
+        let diff =
+            "+++ b/services/disk-usage-monitor-daemon.yaml\n+name: disk-usage-monitor-daemon";
+        assert_eq!(logger.redact(diff), diff);
+        assert_eq!(
+            logger.redact("key=sk-abcdefghijklmnopqrstuv, Authorization: Bearer abcdefgh12"),
+            "key=sk-[REDACTED], Authorization: Bearer [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn test_append_and_rotation() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("debug.log");
+        let path_str = path.to_str().unwrap().to_string();
+        let config = DebugLogConfig {
+            append: true,
+            max_files: 2,
+            ..DebugLogConfig::default()
+        };
+
+        DebugLogger::new(Some(path_str.clone()), &config).log_info("first run");
+        DebugLogger::new(Some(path_str.clone()), &config).log_info("second run");
+        let content = read(&path);
+        assert!(content.contains("first run") && content.contains("second run"));
+
+        // A log over the size limit is rotated when the next run starts
+        std::fs::write(&path, vec![b'x'; 1024 * 1024]).unwrap();
+        let small = DebugLogConfig {
+            max_size_mb: 1,
+            ..config
+        };
+        DebugLogger::new(Some(path_str.clone()), &small).log_info("third run");
+        assert!(read(&path).contains("third run"));
+        assert_eq!(
+            std::fs::metadata(dir.path().join("debug.log.1"))
+                .unwrap()
+                .len(),
+            1024 * 1024
+        );
+    }
 }
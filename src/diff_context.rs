@@ -0,0 +1,206 @@
+/// Controls how much surrounding code is included with each diff hunk
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffContext {
+    /// Number of unchanged lines shown around each change
+    pub lines: u32,
+    /// Whether hunk headers are annotated with the enclosing function, class or impl
+    pub symbols: bool,
+}
+
+impl Default for DiffContext {
+    fn default() -> Self {
+        Self {
+            lines: 3,
+            symbols: true,
+        }
+    }
+}
+
+/// Modifiers that may precede a declaration keyword
+const MODIFIERS: &[&str] = &[
+    "pub ",
+    "pub(crate) ",
+    "pub(super) ",
+    "export ",
+    "default ",
+    "async ",
+    "unsafe ",
+    "extern ",
+    "static ",
+    "public ",
+    "private ",
+    "protected ",
+    "internal ",
+    "abstract ",
+    "final ",
+    "override ",
+];
+
+/// Keywords that start a function, type or module declaration in common languages
+const DECLARATIONS: &[&str] = &[
+    "fn ",
+    "impl ",
+    "impl<",
+    "struct ",
+    "enum ",
+    "trait ",
+    "mod ",
+    "macro_rules! ",
+    "def ",
+    "class ",
+    "function ",
+    "func ",
+    "interface ",
+    "module ",
+    "namespace ",
+];
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Returns a compact symbol name (e.g. `fn decide_diff`) if the line is a declaration
+fn declaration(line: &str) -> Option<String> {
+    let mut rest = line.trim();
+    while let Some(modifier) = MODIFIERS.iter().find(|m| rest.starts_with(*m)) {
+        rest = rest[modifier.len()..].trim_start();
+    }
+
+    let keyword = DECLARATIONS.iter().find(|k| rest.starts_with(*k))?;
+    let name_start = keyword.trim_end().len();
+
+    // Go methods carry their receiver in parentheses before the name
+    let search_from = match rest[name_start..].trim_start().strip_prefix('(') {
+        Some(receiver) => rest.len() - receiver.len() + receiver.find(')').map_or(0, |i| i + 1),
+        None => name_start,
+    };
+    let end = rest[search_from..]
+        .find(['(', '{'])
+        .map_or(rest.len(), |i| search_from + i);
+
+    let symbol = rest[..end].trim_end().trim_end_matches(':').trim_end();
+    if symbol.len() <= keyword.trim_end().len() {
+        return None;
+    }
+    Some(symbol.to_string())
+}
+
+/// Finds the chain of declarations enclosing `line` (0-based), outermost first,
+/// using indentation to decide nesting
+pub fn enclosing_symbol(lines: &[&str], line: usize) -> Option<String> {
+    let last = line.min(lines.len().checked_sub(1)?);
+    let mut threshold: Option<usize> = None;
+    let mut symbols = Vec::new();
+
+    for candidate in lines[..=last].iter().rev() {
+        if candidate.trim().is_empty() {
+            continue;
+        }
+        let indent = indent_of(candidate);
+        // The first non-blank line may itself be a declaration; anything above
+        // only encloses it when it is indented less
+        if threshold.is_none_or(|t| indent < t) {
+            if let Some(symbol) = declaration(candidate) {
+                symbols.push(symbol);
+            }
+            threshold = Some(indent);
+        }
+        if threshold == Some(0) {
+            break;
+        }
+    }
+
+    if symbols.is_empty() {
+        return None;
+    }
+    symbols.reverse();
+    Some(symbols.join(" > "))
+}
+
+/// Parses the new-file start line from a `@@ -a,b +c,d @@` header
+fn hunk_new_start(header: &str) -> Option<usize> {
+    let plus = header
+        .split_whitespace()
+        .find(|part| part.starts_with('+'))?;
+    plus[1..].split(',').next()?.parse().ok()
+}
+
+/// Rewrites the `@@` headers of a single-file unified diff so each names the
+/// symbol enclosing its first changed line in `new_content`
+pub fn annotate_hunks(patch: &str, new_content: &str) -> String {
+    let new_lines: Vec<&str> = new_content.lines().collect();
+    let patch_lines: Vec<&str> = patch.split_inclusive('\n').collect();
+    let mut output = String::with_capacity(patch.len());
+
+    for (i, line) in patch_lines.iter().enumerate() {
+        let header_end = line
+            .strip_prefix("@@ ")
+            .and_then(|rest| rest.find(" @@"))
+            .map(|end| end + 6);
+        let (Some(header_end), Some(start)) = (header_end, hunk_new_start(line)) else {
+            output.push_str(line);
+            continue;
+        };
+
+        let leading_context = patch_lines[i + 1..]
+            .iter()
+            .take_while(|l| l.starts_with(' '))
+            .count();
+        let first_change = (start + leading_context).saturating_sub(1);
+
+        output.push_str(&line[..header_end]);
+        if let Some(symbol) = enclosing_symbol(&new_lines, first_change) {
+            output.push(' ');
+            output.push_str(&symbol);
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_declaration_names() {
+        assert_eq!(
+            declaration("pub fn decide_diff(repo: &Repository) -> Result<()> {"),
+            Some("fn decide_diff".to_string())
+        );
+        assert_eq!(
+            declaration("impl<T: Clone> From<T> for Foo {"),
+            Some("impl<T: Clone> From<T> for Foo".to_string())
+        );
+        assert_eq!(
+            declaration("class Foo(Base):"),
+            Some("class Foo".to_string())
+        );
+        assert_eq!(
+            declaration("func (s *Server) Start(ctx context.Context) error {"),
+            Some("func (s *Server) Start".to_string())
+        );
+        assert_eq!(declaration("let x = fn_call();"), None);
+    }
+
+    #[test]
+    fn test_enclosing_symbol_chain() {
+        let source = "impl Actor {\n    fn ask(&mut self) {\n        let x = 1;\n        let y = 2;\n    }\n}\n";
+        let lines: Vec<&str> = source.lines().collect();
+        assert_eq!(
+            enclosing_symbol(&lines, 3),
+            Some("impl Actor > fn ask".to_string())
+        );
+        assert_eq!(enclosing_symbol(&lines, 0), Some("impl Actor".to_string()));
+    }
+
+    #[test]
+    fn test_annotate_hunks() {
+        let content = "fn a() {\n    one();\n    two();\n}\n";
+        let patch = "--- a/x.rs\n+++ b/x.rs\n@@ -1,3 +1,3 @@ old\n fn a() {\n-    uno();\n+    one();\n     two();\n";
+        let annotated = annotate_hunks(patch, content);
+        assert!(annotated.contains("@@ -1,3 +1,3 @@ fn a\n"));
+        assert!(annotated.contains("+    one();\n"));
+    }
+}
@@ -1,17 +1,81 @@
-use git2::{Repository, Tree};
+use git2::{Diff, DiffDelta, DiffFindOptions, DiffOptions, Patch, Repository, Tree};
+use std::path::Path;
 use std::process::Command;
 
+use crate::diff_context::{annotate_hunks, DiffContext};
+
 pub fn get_repo() -> Result<Repository, git2::Error> {
     Repository::discover(".")
 }
 
-pub fn staged_files(repo: &Repository) -> Result<Vec<String>, git2::Error> {
+/// Diffs HEAD against the index with rename and copy detection enabled
+fn staged_diff<'a>(repo: &'a Repository, context: &DiffContext) -> Result<Diff<'a>, git2::Error> {
     let idx = repo.index()?;
     let mut head: Option<Tree> = None;
     if let Ok(h) = repo.head() {
         head = Some(h.peel_to_tree()?);
     }
-    let diff = repo.diff_tree_to_index(head.as_ref(), Some(&idx), None)?;
+    let mut diff =
+        repo.diff_tree_to_index(head.as_ref(), Some(&idx), Some(&mut diff_options(context)))?;
+    find_renames(&mut diff)?;
+    Ok(diff)
+}
+
+fn diff_options(context: &DiffContext) -> DiffOptions {
+    let mut opts = DiffOptions::new();
+    opts.context_lines(context.lines);
+    opts
+}
+
+/// Pairs deleted/added and modified/added files so moves render as `rename from/to`
+/// headers with only the content delta instead of a full delete plus add
+fn find_renames(diff: &mut Diff<'_>) -> Result<(), git2::Error> {
+    let mut opts = DiffFindOptions::new();
+    opts.renames(true).copies(true);
+    diff.find_similar(Some(&mut opts))
+}
+
+/// Renders every delta accepted by `include` as a unified patch, naming the
+/// enclosing symbol in each hunk header when enabled
+fn render_patches(
+    repo: &Repository,
+    diff: &Diff<'_>,
+    context: &DiffContext,
+    include: impl Fn(&DiffDelta<'_>) -> bool,
+) -> Result<String, git2::Error> {
+    let mut ret = String::new();
+    for (idx, delta) in diff.deltas().enumerate() {
+        if !include(&delta) {
+            continue;
+        }
+        let Some(mut patch) = Patch::from_diff(diff, idx)? else {
+            let old_path = delta.old_file().path().unwrap_or(Path::new(""));
+            let new_path = delta.new_file().path().unwrap_or(Path::new(""));
+            ret.push_str(&format!(
+                "diff --git a/{0} b/{1}\nBinary files a/{0} and b/{1} differ\n",
+                old_path.display(),
+                new_path.display()
+            ));
+            continue;
+        };
+        let buf = patch.to_buf()?;
+        let text = buf.as_str().unwrap_or("");
+
+        let new_blob = delta.new_file().id();
+        if context.symbols && !new_blob.is_zero() {
+            if let Ok(blob) = repo.find_blob(new_blob) {
+                let content = String::from_utf8_lossy(blob.content());
+                ret.push_str(&annotate_hunks(text, &content));
+                continue;
+            }
+        }
+        ret.push_str(text);
+    }
+    Ok(ret)
+}
+
+pub fn staged_files(repo: &Repository) -> Result<Vec<String>, git2::Error> {
+    let diff = staged_diff(repo, &DiffContext::default())?;
     Ok(diff
         .deltas()
         .map(|d| {
@@ -21,25 +85,18 @@ pub fn staged_files(repo: &Repository) -> Result<Vec<String>, git2::Error> {
         .collect())
 }
 
-pub fn diff(repo: &Repository, files: &[String]) -> Result<String, git2::Error> {
-    let mut ret = String::new();
-
-    let idx = repo.index()?;
-    let mut head: Option<Tree> = None;
-    if let Ok(h) = repo.head() {
-        head = Some(h.peel_to_tree()?);
-    }
-    let diff = repo.diff_tree_to_index(head.as_ref(), Some(&idx), None)?;
-    diff.print(git2::DiffFormat::Patch, |delta, _, line| {
-        if let Some(path) = delta.new_file().path() {
-            if files.contains(&path.to_str().unwrap_or("").to_string()) {
-                ret.push(line.origin());
-                ret.push_str(std::str::from_utf8(line.content()).unwrap_or(""));
-            }
-        }
-        true
-    })?;
-    Ok(ret)
+pub fn diff(
+    repo: &Repository,
+    files: &[String],
+    context: &DiffContext,
+) -> Result<String, git2::Error> {
+    let diff = staged_diff(repo, context)?;
+    render_patches(repo, &diff, context, |delta| {
+        delta
+            .new_file()
+            .path()
+            .is_some_and(|path| files.contains(&path.to_str().unwrap_or("").to_string()))
+    })
 }
 
 // idk how this is really supposed to work
@@ -60,22 +117,189 @@ pub fn diff(repo: &Repository, files: &[String]) -> Result<String, git2::Error>
 //     Ok(())
 // }
 
-pub fn get_last_commit_diff(repo: &Repository) -> Result<String, git2::Error> {
-    let mut ret = String::new();
+pub fn get_last_commit_diff(
+    repo: &Repository,
+    context: &DiffContext,
+) -> Result<String, git2::Error> {
     let head = repo.head()?;
     let head_commit = head.peel_to_commit()?;
 
-    if let Some(parent) = head_commit.parent(0).ok() {
-        let diff =
-            repo.diff_tree_to_tree(Some(&parent.tree()?), Some(&head_commit.tree()?), None)?;
+    if let Ok(parent) = head_commit.parent(0) {
+        let mut diff = repo.diff_tree_to_tree(
+            Some(&parent.tree()?),
+            Some(&head_commit.tree()?),
+            Some(&mut diff_options(context)),
+        )?;
+        find_renames(&mut diff)?;
 
-        diff.print(git2::DiffFormat::Patch, |_, _, line| {
-            ret.push(line.origin());
-            ret.push_str(std::str::from_utf8(line.content()).unwrap_or(""));
-            true
-        })?;
+        return render_patches(repo, &diff, context, |_| true);
     }
-    Ok(ret)
+    Ok(String::new())
+}
+
+/// The branch pull requests most likely target: origin's default branch, then
+/// a local or remote `main` or `master`
+pub fn default_base(repo: &Repository) -> Option<String> {
+    if let Ok(reference) = repo.find_reference("refs/remotes/origin/HEAD") {
+        if let Some(target) = reference.symbolic_target() {
+            return Some(target.trim_start_matches("refs/remotes/").to_string());
+        }
+    }
+    ["main", "master", "origin/main", "origin/master"]
+        .into_iter()
+        .find(|name| repo.revparse_single(name).is_ok())
+        .map(String::from)
+}
+
+/// The diff of HEAD against where it branched off `base`, and the messages of
+/// the commits since, oldest first
+pub fn branch_changes(
+    repo: &Repository,
+    base: &str,
+    context: &DiffContext,
+) -> Result<(String, Vec<String>), git2::Error> {
+    let head = repo.head()?.peel_to_commit()?;
+    let base = repo.revparse_single(base)?.peel_to_commit()?;
+    let fork = repo.find_commit(repo.merge_base(base.id(), head.id())?)?;
+
+    let mut diff = repo.diff_tree_to_tree(
+        Some(&fork.tree()?),
+        Some(&head.tree()?),
+        Some(&mut diff_options(context)),
+    )?;
+    find_renames(&mut diff)?;
+    let diff = render_patches(repo, &diff, context, |_| true)?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(head.id())?;
+    revwalk.hide(fork.id())?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+    let mut messages = Vec::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        if let Some(message) = commit.message() {
+            messages.push(message.trim().to_string());
+        }
+    }
+    Ok((diff, messages))
+}
+
+/// Returns the name of the checked-out branch, if HEAD points to one
+pub fn current_branch(repo: &Repository) -> Option<String> {
+    let head = repo.head().ok()?;
+    if !head.is_branch() {
+        return None;
+    }
+    head.shorthand().map(String::from)
+}
+
+/// Returns the paths changed by the last commit
+pub fn last_commit_files(repo: &Repository) -> Result<Vec<String>, git2::Error> {
+    let head_commit = repo.head()?.peel_to_commit()?;
+    let parent_tree = match head_commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&head_commit.tree()?), None)?;
+    Ok(diff
+        .deltas()
+        .filter_map(|d| d.new_file().path()?.to_str().map(String::from))
+        .collect())
+}
+
+/// Returns up to `count` recent non-merge commit messages reachable from HEAD,
+/// skipping the first `skip` of them (e.g. the commit being amended)
+pub fn recent_commit_messages(
+    repo: &Repository,
+    count: usize,
+    skip: usize,
+) -> Result<Vec<String>, git2::Error> {
+    let mut revwalk = repo.revwalk()?;
+    if revwalk.push_head().is_err() {
+        // No commits yet
+        return Ok(Vec::new());
+    }
+
+    let mut messages = Vec::new();
+    for oid in revwalk.skip(skip) {
+        if messages.len() >= count {
+            break;
+        }
+        let commit = repo.find_commit(oid?)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        if let Some(message) = commit.message() {
+            if !message.trim().is_empty() {
+                messages.push(message.trim().to_string());
+            }
+        }
+    }
+    Ok(messages)
+}
+
+/// Returns up to `count` distinct `Name <email>` authors from recent history,
+/// excluding the current user
+pub fn recent_authors(repo: &Repository, count: usize) -> Result<Vec<String>, git2::Error> {
+    let mut revwalk = repo.revwalk()?;
+    if revwalk.push_head().is_err() {
+        // No commits yet
+        return Ok(Vec::new());
+    }
+    let own_email = repo
+        .signature()
+        .ok()
+        .and_then(|sig| sig.email().map(String::from));
+
+    let mut authors: Vec<String> = Vec::new();
+    // Look at a bounded window of history so large repos stay fast
+    for oid in revwalk.take(500) {
+        if authors.len() >= count {
+            break;
+        }
+        let commit = repo.find_commit(oid?)?;
+        let author = commit.author();
+        let (Some(name), Some(email)) = (author.name(), author.email()) else {
+            continue;
+        };
+        if Some(email) == own_email.as_deref() {
+            continue;
+        }
+        let identity = format!("{} <{}>", name, email);
+        if !authors.contains(&identity) {
+            authors.push(identity);
+        }
+    }
+    Ok(authors)
+}
+
+/// Returns the configured `Name <email>` identity, if both are set
+pub fn user_identity(repo: &Repository) -> Option<String> {
+    let sig = repo.signature().ok()?;
+    Some(format!("{} <{}>", sig.name()?, sig.email()?))
+}
+
+/// Whether `format.signoff` is enabled in the git config
+pub fn signoff_enabled(repo: &Repository) -> bool {
+    repo.config()
+        .and_then(|config| config.get_bool("format.signoff"))
+        .unwrap_or(false)
+}
+
+/// Returns the URLs of all configured remotes
+pub fn remote_urls(repo: &Repository) -> Vec<String> {
+    let Ok(names) = repo.remotes() else {
+        return Vec::new();
+    };
+    names
+        .iter()
+        .flatten()
+        .filter_map(|name| repo.find_remote(name).ok())
+        .filter_map(|remote| remote.url().map(String::from))
+        .collect()
 }
 
 pub fn has_staged_changes(repo: &Repository) -> Result<bool, git2::Error> {
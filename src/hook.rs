@@ -0,0 +1,153 @@
+use std::path::{Path, PathBuf};
+use std::process;
+
+use colored::Colorize;
+
+use crate::cli::{HookAction, HookArgs};
+use crate::git;
+
+/// The git hook turbocommit installs
+const HOOK_NAME: &str = "prepare-commit-msg";
+
+/// Marks hooks written by turbocommit, so foreign hooks aren't overwritten or removed
+const MARKER: &str = "# Installed by turbocommit";
+
+/// Runs `turbocommit hook run`; a failure leaves the message empty instead of
+/// blocking the commit
+fn script() -> String {
+    format!(
+        "#!/bin/sh\n{}\nturbocommit hook run \"$@\" || true\n",
+        MARKER
+    )
+}
+
+/// The hooks directory of the repository, honoring `core.hooksPath`
+fn hooks_dir() -> anyhow::Result<PathBuf> {
+    let repo = git::get_repo()?;
+    let configured = repo
+        .config()
+        .ok()
+        .and_then(|config| config.get_path("core.hooksPath").ok());
+    Ok(match configured {
+        Some(dir) if dir.is_absolute() => dir,
+        Some(dir) => repo.workdir().unwrap_or(repo.path()).join(dir),
+        None => repo.path().join("hooks"),
+    })
+}
+
+fn is_ours(path: &Path) -> bool {
+    std::fs::read_to_string(path).is_ok_and(|content| content.contains(MARKER))
+}
+
+fn install(force: bool) -> anyhow::Result<PathBuf> {
+    let dir = hooks_dir()?;
+    let path = dir.join(HOOK_NAME);
+    if path.exists() && !is_ours(&path) && !force {
+        anyhow::bail!(
+            "{} already exists, run with --force to replace it",
+            path.display()
+        );
+    }
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&path, script())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(path)
+}
+
+fn uninstall() -> anyhow::Result<PathBuf> {
+    let path = hooks_dir()?.join(HOOK_NAME);
+    if !path.exists() {
+        anyhow::bail!("No {} hook is installed", HOOK_NAME);
+    }
+    if !is_ours(&path) {
+        anyhow::bail!(
+            "{} wasn't installed by turbocommit, leaving it alone",
+            path.display()
+        );
+    }
+    std::fs::remove_file(&path)?;
+    Ok(path)
+}
+
+/// Installs or removes the hook. `hook run` is handled by the commit flow.
+pub fn run(args: &HookArgs) {
+    let result = match &args.action {
+        HookAction::Install { force } => install(*force).map(|path| {
+            format!(
+                "Installed {}, `git commit` now starts with a generated message.",
+                path.display()
+            )
+        }),
+        HookAction::Uninstall => uninstall().map(|path| format!("Removed {}", path.display())),
+        HookAction::Run { .. } => return,
+    };
+    match result {
+        Ok(message) => {
+            println!("{}", message.green());
+            process::exit(0);
+        }
+        Err(err) => {
+            println!("{}", err.to_string().red());
+            process::exit(1);
+        }
+    }
+}
+
+/// Whether the hook should write a message: only for a plain `git commit`,
+/// not when a message, template, merge, squash or amended commit is given,
+/// and not when the file already has a message
+pub fn wants_message(file: &Path, source: Option<&str>) -> bool {
+    if source.is_some_and(|source| !source.is_empty()) {
+        return false;
+    }
+    std::fs::read_to_string(file).is_ok_and(|content| {
+        content
+            .lines()
+            .all(|line| line.trim().is_empty() || line.starts_with('#'))
+    })
+}
+
+/// Puts the message above the comments git wrote into the message file
+pub fn write_message(file: &Path, message: &str) -> anyhow::Result<()> {
+    let existing = std::fs::read_to_string(file).unwrap_or_default();
+    std::fs::write(file, format!("{}\n{}", message.trim_end(), existing))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_wants_and_write_message() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("COMMIT_EDITMSG");
+        std::fs::write(&file, "\n# Please enter the commit message\n").unwrap();
+        assert!(wants_message(&file, None));
+        assert!(wants_message(&file, Some("")));
+        assert!(!wants_message(&file, Some("message")));
+        assert!(!wants_message(&file, Some("commit")));
+
+        write_message(&file, "feat: add x\n\nBody.\n").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "feat: add x\n\nBody.\n\n# Please enter the commit message\n"
+        );
+        assert!(!wants_message(&file, None));
+    }
+
+    #[test]
+    fn test_script_is_marked() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(HOOK_NAME);
+        std::fs::write(&path, script()).unwrap();
+        assert!(is_ours(&path));
+        std::fs::write(&path, "#!/bin/sh\nexit 0\n").unwrap();
+        assert!(!is_ours(&path));
+    }
+}
@@ -11,11 +11,15 @@ use jj_lib::workspace::Workspace;
 use pollster::FutureExt;
 use tokio::io::AsyncReadExt;
 
+use crate::diff_context::{annotate_hunks, DiffContext};
+
 /// Represents the VCS type being used
 #[derive(Debug, Clone, PartialEq)]
 pub enum VcsType {
     Git,
     Jujutsu,
+    /// No repository is used, the diff is given with `--diff`
+    Patch,
 }
 
 /// Detects which VCS is being used in the current directory
@@ -53,6 +57,33 @@ pub fn detect_vcs() -> anyhow::Result<VcsType> {
     Err(anyhow::anyhow!("No supported VCS repository found. Please run this command from within a git or jj repository."))
 }
 
+/// Returns the root of the enclosing jj workspace or git work tree
+pub fn repo_root() -> Option<std::path::PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    if let Some(root) = cwd.ancestors().find(|dir| dir.join(".jj").is_dir()) {
+        return Some(root.to_path_buf());
+    }
+    git2::Repository::discover(&cwd)
+        .ok()?
+        .workdir()
+        .map(Path::to_path_buf)
+}
+
+/// Returns the remote URLs of the git repository backing the jj workspace
+pub fn get_jj_remote_urls() -> Vec<String> {
+    let Some(store) = repo_root().map(|root| root.join(".jj/repo/store")) else {
+        return Vec::new();
+    };
+    // `git_target` holds the path of the backing git repository, relative to the store
+    let Ok(target) = std::fs::read_to_string(store.join("git_target")) else {
+        return Vec::new();
+    };
+    match git2::Repository::open(store.join(target.trim())) {
+        Ok(repo) => crate::git::remote_urls(&repo),
+        Err(_) => Vec::new(),
+    }
+}
+
 /// Validates that a revision string is safe and not a complex expression
 pub fn validate_revision_id(rev: &str) -> anyhow::Result<()> {
     // Simple validation: should not be empty
@@ -151,164 +182,151 @@ fn resolve_revision_to_commit_id(
     }
 }
 
-/// Gets the diff for Jujutsu VCS for specific files
-pub fn get_jj_diff_for_files(revision: Option<&str>, files: &[String]) -> anyhow::Result<String> {
-    let config = StackedConfig::with_defaults();
-    let user_settings = UserSettings::from_config(config)?;
-    let store_factories = jj_lib::repo::StoreFactories::default();
-    let working_copy_factories = jj_lib::workspace::default_working_copy_factories();
-
-    let workspace = Workspace::load(
-        &user_settings,
-        Path::new("."),
-        &store_factories,
-        &working_copy_factories,
-    )?;
-    let repo = workspace.repo_loader().load_at_head()?;
+/// Minimum similarity (in percent) for two files to be paired as a rename or copy,
+/// mirroring git's default `-M50%`
+const SIMILARITY_THRESHOLD: u32 = 50;
 
-    // Resolve revision (default to @)
-    let rev = revision.unwrap_or("@");
-    let commit = if rev == "@" {
-        // Get the working copy commit
-        let wc_commit_id = repo
-            .view()
-            .get_wc_commit_id(workspace.workspace_name())
-            .ok_or_else(|| anyhow::anyhow!("No working copy commit found"))?;
-        repo.store().get_commit(wc_commit_id)?
-    } else {
-        // Resolve revision using jj's index for prefix matching
-        let commit_id = resolve_revision_to_commit_id(&repo, rev)?;
-        repo.store().get_commit(&commit_id)?
-    };
+/// How a file at a new path relates to its source path
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PairKind {
+    Rename,
+    Copy,
+}
 
-    let tree = commit.tree()?;
-    let parent_tree = commit.parent_tree(repo.as_ref())?;
+/// A detected rename or copy between two paths
+#[derive(Debug, Clone, PartialEq)]
+struct FilePair {
+    kind: PairKind,
+    from: String,
+    to: String,
+    similarity: u32,
+}
 
-    // Generate proper diff using jj-lib API
-    // tree and parent_tree are already MergedTree instances
-    let merged_tree = tree;
-    let merged_parent_tree = parent_tree;
-    let matcher = EverythingMatcher;
+/// Percentage of lines shared between two file contents
+fn similarity(source: &[u8], target: &[u8]) -> u32 {
+    if source == target {
+        return 100;
+    }
+    let source_text = String::from_utf8_lossy(source);
+    let target_text = String::from_utf8_lossy(target);
+    let total = source_text.lines().count() + target_text.lines().count();
+    if total == 0 {
+        return 0;
+    }
 
-    let diff_stream = merged_parent_tree.diff_stream(&merged_tree, &matcher);
-    let mut diff_result = String::new();
+    let mut remaining: std::collections::HashMap<&str, usize> = std::collections::HashMap::new();
+    for line in source_text.lines() {
+        *remaining.entry(line).or_default() += 1;
+    }
+    let mut common = 0;
+    for line in target_text.lines() {
+        if let Some(count) = remaining.get_mut(line) {
+            if *count > 0 {
+                *count -= 1;
+                common += 1;
+            }
+        }
+    }
 
-    // Collect all diff entries and iterate through them
-    let entries: Vec<jj_lib::merged_tree::TreeDiffEntry> =
-        diff_stream.collect::<Vec<_>>().block_on();
-    for entry in entries {
-        let path = &entry.path;
-        let path_str = path.as_internal_file_string();
+    (common * 2 * 100 / total) as u32
+}
 
-        // Only include files that are in the selected list
-        if !files.contains(&path_str.to_string()) {
+/// Pairs added files with deleted files (renames) or modified files (copies).
+/// Each deleted file is consumed by at most one rename; copy sources can be reused.
+fn detect_file_pairs(
+    deleted: &[(String, Vec<u8>)],
+    added: &[(String, Vec<u8>)],
+    modified: &[(String, Vec<u8>)],
+) -> Vec<FilePair> {
+    let mut pairs = Vec::new();
+    let mut consumed = vec![false; deleted.len()];
+
+    for (to, target) in added {
+        let best_rename = deleted
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !consumed[*i])
+            .map(|(i, (_, source))| (i, similarity(source, target)))
+            .filter(|(_, score)| *score >= SIMILARITY_THRESHOLD)
+            .max_by_key(|(_, score)| *score);
+
+        if let Some((i, score)) = best_rename {
+            consumed[i] = true;
+            pairs.push(FilePair {
+                kind: PairKind::Rename,
+                from: deleted[i].0.clone(),
+                to: to.clone(),
+                similarity: score,
+            });
             continue;
         }
 
-        // Get source (before) and target (after) values
-        let diff = entry
-            .values
-            .as_ref()
-            .map_err(|e| anyhow::anyhow!("Diff error: {}", e))?;
-        let source_value = &diff.before;
-        let target_value = &diff.after;
-
-        // Determine change type and generate appropriate diff
-        match (source_value.as_resolved(), target_value.as_resolved()) {
-            // File deleted (exists in parent, absent in current)
-            (Some(Some(TreeValue::File { id: source_id, .. })), Some(None)) => {
-                diff_result.push_str(&format!("diff --git a/{} b/{}\n", path_str, path_str));
-                diff_result.push_str(&format!("deleted file mode 100644\n"));
-                diff_result.push_str(&format!("--- a/{}\n", path_str));
-                diff_result.push_str(&format!("+++ /dev/null\n"));
-
-                let content = read_file_content(repo.store(), path, &source_id).block_on()?;
-                diff_result.push_str(&format_deletion(&content));
-            }
-
-            // File added (absent in parent, exists in current)
-            (Some(None), Some(Some(TreeValue::File { id: target_id, .. }))) => {
-                diff_result.push_str(&format!("diff --git a/{} b/{}\n", path_str, path_str));
-                diff_result.push_str(&format!("new file mode 100644\n"));
-                diff_result.push_str(&format!("--- /dev/null\n"));
-                diff_result.push_str(&format!("+++ b/{}\n", path_str));
-
-                let content = read_file_content(repo.store(), path, &target_id).block_on()?;
-                diff_result.push_str(&format_addition(&content));
-            }
-
-            // File modified
-            (
-                Some(Some(TreeValue::File {
-                    id: source_id,
-                    executable: source_exec,
-                    ..
-                })),
-                Some(Some(TreeValue::File {
-                    id: target_id,
-                    executable: target_exec,
-                    ..
-                })),
-            ) if source_id != target_id || source_exec != target_exec => {
-                diff_result.push_str(&format!("diff --git a/{} b/{}\n", path_str, path_str));
-
-                if source_exec != target_exec {
-                    if *target_exec {
-                        diff_result.push_str("old mode 100644\n");
-                        diff_result.push_str("new mode 100755\n");
-                    } else {
-                        diff_result.push_str("old mode 100755\n");
-                        diff_result.push_str("new mode 100644\n");
-                    }
-                }
-
-                diff_result.push_str(&format!("--- a/{}\n", path_str));
-                diff_result.push_str(&format!("+++ b/{}\n", path_str));
-
-                let source_content =
-                    read_file_content(repo.store(), path, &source_id).block_on()?;
-                let target_content =
-                    read_file_content(repo.store(), path, &target_id).block_on()?;
-
-                diff_result.push_str(&format_unified_diff(&source_content, &target_content)?);
-            }
+        let best_copy = modified
+            .iter()
+            .map(|(from, source)| (from, similarity(source, target)))
+            .filter(|(_, score)| *score >= SIMILARITY_THRESHOLD)
+            .max_by_key(|(_, score)| *score);
+
+        if let Some((from, score)) = best_copy {
+            pairs.push(FilePair {
+                kind: PairKind::Copy,
+                from: from.clone(),
+                to: to.clone(),
+                similarity: score,
+            });
+        }
+    }
 
-            // Symlink changes
-            (
-                Some(Some(TreeValue::Symlink(source_id))),
-                Some(Some(TreeValue::Symlink(target_id))),
-            ) if source_id != target_id => {
-                diff_result.push_str(&format!("diff --git a/{} b/{}\n", path_str, path_str));
-                diff_result.push_str(&format!("--- a/{}\n", path_str));
-                diff_result.push_str(&format!("+++ b/{}\n", path_str));
-                diff_result.push_str("@@ -1 +1 @@\n");
+    pairs
+}
 
-                let source_target = read_symlink(repo.store(), path, &source_id).block_on()?;
-                let target_target = read_symlink(repo.store(), path, &target_id).block_on()?;
-                diff_result.push_str(&format!("-{}\n", source_target));
-                diff_result.push_str(&format!("+{}\n", target_target));
-            }
+/// Formats a rename or copy with git-style headers and only the content delta
+fn format_file_pair(
+    pair: &FilePair,
+    source: &[u8],
+    target: &[u8],
+    context: &DiffContext,
+) -> anyhow::Result<String> {
+    let (from_label, to_label) = match pair.kind {
+        PairKind::Rename => ("rename from", "rename to"),
+        PairKind::Copy => ("copy from", "copy to"),
+    };
 
-            // File type changes (e.g., file to symlink)
-            (Some(Some(source)), Some(Some(target)))
-                if std::mem::discriminant(source) != std::mem::discriminant(target) =>
-            {
-                diff_result.push_str(&format!("diff --git a/{} b/{}\n", path_str, path_str));
-                diff_result.push_str(&format!("--- a/{}\n", path_str));
-                diff_result.push_str(&format!("+++ b/{}\n", path_str));
-                diff_result.push_str(&format!("File type changed\n"));
-            }
+    let mut output = format!("diff --git a/{} b/{}\n", pair.from, pair.to);
+    output.push_str(&format!("similarity index {}%\n", pair.similarity));
+    output.push_str(&format!("{} {}\n", from_label, pair.from));
+    output.push_str(&format!("{} {}\n", to_label, pair.to));
 
-            // No change or unsupported
-            _ => {}
-        }
+    if source != target {
+        output.push_str(&format!("--- a/{}\n", pair.from));
+        output.push_str(&format!("+++ b/{}\n", pair.to));
+        output.push_str(&format_unified_diff(source, target, context)?);
     }
 
-    Ok(diff_result)
+    Ok(output)
+}
+
+/// Gets the diff for Jujutsu VCS for specific files
+pub fn get_jj_diff_for_files(
+    revision: Option<&str>,
+    files: &[String],
+    context: &DiffContext,
+) -> anyhow::Result<String> {
+    render_jj_diff(revision, Some(files), context)
 }
 
 /// Gets the diff for Jujutsu VCS
-pub fn get_jj_diff(revision: Option<&str>) -> anyhow::Result<String> {
+pub fn get_jj_diff(revision: Option<&str>, context: &DiffContext) -> anyhow::Result<String> {
+    render_jj_diff(revision, None, context)
+}
+
+/// Renders the diff of a revision against its parent, optionally limited to `files`.
+/// Renames and copies are detected and rendered with only their content delta.
+fn render_jj_diff(
+    revision: Option<&str>,
+    files: Option<&[String]>,
+    context: &DiffContext,
+) -> anyhow::Result<String> {
     let config = StackedConfig::with_defaults();
     let user_settings = UserSettings::from_config(config)?;
     let store_factories = jj_lib::repo::StoreFactories::default();
@@ -352,10 +370,86 @@ pub fn get_jj_diff(revision: Option<&str>) -> anyhow::Result<String> {
     // Collect all diff entries and iterate through them
     let entries: Vec<jj_lib::merged_tree::TreeDiffEntry> =
         diff_stream.collect::<Vec<_>>().block_on();
+
+    // First pass: gather deleted, added and modified file contents for rename/copy detection
+    let mut deleted = Vec::new();
+    let mut added = Vec::new();
+    let mut modified = Vec::new();
+    for entry in &entries {
+        let path = &entry.path;
+        let path_str = path.as_internal_file_string().to_string();
+        let diff = entry
+            .values
+            .as_ref()
+            .map_err(|e| anyhow::anyhow!("Diff error: {}", e))?;
+
+        match (diff.before.as_resolved(), diff.after.as_resolved()) {
+            (Some(Some(TreeValue::File { id, .. })), Some(None)) => {
+                deleted.push((
+                    path_str,
+                    read_file_content(repo.store(), path, id).block_on()?,
+                ));
+            }
+            (Some(None), Some(Some(TreeValue::File { id, .. }))) => {
+                added.push((
+                    path_str,
+                    read_file_content(repo.store(), path, id).block_on()?,
+                ));
+            }
+            (
+                Some(Some(TreeValue::File { id: source_id, .. })),
+                Some(Some(TreeValue::File { id: target_id, .. })),
+            ) if source_id != target_id => {
+                modified.push((
+                    path_str,
+                    read_file_content(repo.store(), path, source_id).block_on()?,
+                ));
+            }
+            _ => {}
+        }
+    }
+    let pairs = detect_file_pairs(&deleted, &added, &modified);
+    let content_of = |list: &[(String, Vec<u8>)], path: &str| -> Vec<u8> {
+        list.iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, content)| content.clone())
+            .unwrap_or_default()
+    };
+
     for entry in entries {
         let path = &entry.path;
         let path_str = path.as_internal_file_string();
 
+        // Renamed-away sources are rendered together with their target
+        if pairs
+            .iter()
+            .any(|p| p.kind == PairKind::Rename && p.from == path_str)
+        {
+            continue;
+        }
+
+        // Render renames and copies at the position of their target path
+        if let Some(pair) = pairs.iter().find(|p| p.to == path_str) {
+            let selected =
+                files.is_none_or(|files| files.contains(&pair.from) || files.contains(&pair.to));
+            if selected {
+                let source = match pair.kind {
+                    PairKind::Rename => content_of(&deleted, &pair.from),
+                    PairKind::Copy => content_of(&modified, &pair.from),
+                };
+                let target = content_of(&added, &pair.to);
+                diff_result.push_str(&format_file_pair(pair, &source, &target, context)?);
+            }
+            continue;
+        }
+
+        // Only include files that are in the selected list
+        if let Some(files) = files {
+            if !files.contains(&path_str.to_string()) {
+                continue;
+            }
+        }
+
         // Get source (before) and target (after) values
         let diff = entry
             .values
@@ -367,27 +461,24 @@ pub fn get_jj_diff(revision: Option<&str>) -> anyhow::Result<String> {
         // Determine change type and generate appropriate diff
         match (source_value.as_resolved(), target_value.as_resolved()) {
             // File deleted (exists in parent, absent in current)
-            (Some(Some(TreeValue::File { id: source_id, .. })), Some(None)) => {
+            (Some(Some(TreeValue::File { .. })), Some(None)) => {
                 diff_result.push_str(&format!("diff --git a/{} b/{}\n", path_str, path_str));
                 diff_result.push_str(&format!("deleted file mode 100644\n"));
                 diff_result.push_str(&format!("--- a/{}\n", path_str));
                 diff_result.push_str(&format!("+++ /dev/null\n"));
 
-                let content = read_file_content(repo.store(), path, &source_id).block_on()?;
-                diff_result.push_str(&format_deletion(&content));
+                diff_result.push_str(&format_deletion(&content_of(&deleted, path_str)));
             }
 
             // File added (absent in parent, exists in current)
-            (Some(None), Some(Some(TreeValue::File { id: target_id, .. }))) => {
+            (Some(None), Some(Some(TreeValue::File { .. }))) => {
                 diff_result.push_str(&format!("diff --git a/{} b/{}\n", path_str, path_str));
                 diff_result.push_str(&format!("new file mode 100644\n"));
                 diff_result.push_str(&format!("--- /dev/null\n"));
                 diff_result.push_str(&format!("+++ b/{}\n", path_str));
 
-                let content = read_file_content(repo.store(), path, &target_id).block_on()?;
-                diff_result.push_str(&format_addition(&content));
+                diff_result.push_str(&format_addition(&content_of(&added, path_str)));
             }
-
             // File modified
             (
                 Some(Some(TreeValue::File {
@@ -421,7 +512,11 @@ pub fn get_jj_diff(revision: Option<&str>) -> anyhow::Result<String> {
                 let target_content =
                     read_file_content(repo.store(), path, &target_id).block_on()?;
 
-                diff_result.push_str(&format_unified_diff(&source_content, &target_content)?);
+                diff_result.push_str(&format_unified_diff(
+                    &source_content,
+                    &target_content,
+                    context,
+                )?);
             }
 
             // Symlink changes
@@ -504,55 +599,78 @@ fn format_deletion(content: &[u8]) -> String {
     output
 }
 
-/// Generate unified diff format between two file contents
-fn format_unified_diff(source: &[u8], target: &[u8]) -> anyhow::Result<String> {
+/// Generate unified diff format between two file contents, keeping `context.lines`
+/// unchanged lines around each change and naming the enclosing symbol when enabled
+fn format_unified_diff(
+    source: &[u8],
+    target: &[u8],
+    context: &DiffContext,
+) -> anyhow::Result<String> {
     let source_text = String::from_utf8_lossy(source);
     let target_text = String::from_utf8_lossy(target);
 
     let source_lines: Vec<&str> = source_text.lines().collect();
     let target_lines: Vec<&str> = target_text.lines().collect();
+    let len = source_lines.len().max(target_lines.len());
+    let differs = |i: usize| source_lines.get(i) != target_lines.get(i);
+
+    // Find change regions, widen them by the context and merge the ones that touch
+    let extra = context.lines as usize;
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    let mut i = 0;
+    while i < len {
+        if !differs(i) {
+            i += 1;
+            continue;
+        }
+        let change_start = i;
+        while i < len && differs(i) {
+            i += 1;
+        }
+        let start = change_start.saturating_sub(extra);
+        let end = (i + extra).min(len);
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
 
     let mut output = String::new();
+    for (start, end) in hunks {
+        let src_count = end.min(source_lines.len()).saturating_sub(start);
+        let tgt_count = end.min(target_lines.len()).saturating_sub(start);
+        output.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            start + 1,
+            src_count,
+            start + 1,
+            tgt_count
+        ));
 
-    // Track hunks
-    let mut i = 0;
-    while i < source_lines.len().max(target_lines.len()) {
-        // Find the start of a change region
-        if source_lines.get(i).unwrap_or(&"") != target_lines.get(i).unwrap_or(&"") {
-            let hunk_start = i;
-            let mut hunk_len = 0;
-            // Find the end of the contiguous change region
-            while i < source_lines.len().max(target_lines.len())
-                && source_lines.get(i).unwrap_or(&"") != target_lines.get(i).unwrap_or(&"")
-            {
-                hunk_len += 1;
-                i += 1;
+        let mut j = start;
+        while j < end {
+            if !differs(j) {
+                output.push_str(&format!(" {}\n", source_lines[j]));
+                j += 1;
+                continue;
             }
-            // Calculate hunk header line numbers and counts
-            let src_hunk_start = hunk_start + 1;
-            let tgt_hunk_start = hunk_start + 1;
-            let src_hunk_count = hunk_len;
-            let tgt_hunk_count = hunk_len;
-            output.push_str(&format!(
-                "@@ -{},{} +{},{} @@\n",
-                src_hunk_start, src_hunk_count, tgt_hunk_start, tgt_hunk_count
-            ));
-            // Output the changed lines in the hunk
-            for j in hunk_start..(hunk_start + hunk_len) {
-                let source_line = source_lines.get(j).unwrap_or(&"");
-                let target_line = target_lines.get(j).unwrap_or(&"");
-                if !source_line.is_empty() {
-                    output.push_str(&format!("-{}\n", source_line));
-                }
-                if !target_line.is_empty() {
-                    output.push_str(&format!("+{}\n", target_line));
-                }
+            // Emit a whole run of removals before its additions
+            let run_start = j;
+            while j < end && differs(j) {
+                j += 1;
+            }
+            for line in source_lines.iter().take(j).skip(run_start) {
+                output.push_str(&format!("-{}\n", line));
+            }
+            for line in target_lines.iter().take(j).skip(run_start) {
+                output.push_str(&format!("+{}\n", line));
             }
-        } else {
-            i += 1;
         }
     }
 
+    if context.symbols {
+        output = annotate_hunks(&output, &target_text);
+    }
     Ok(output)
 }
 
@@ -595,9 +713,153 @@ pub fn get_jj_description(revision: Option<&str>) -> anyhow::Result<Option<Strin
     }
 }
 
-/// Sets the description for a Jujutsu revision by rewriting the target commit
-pub fn set_jj_description(revision: Option<&str>, description: &str) -> anyhow::Result<()> {
-    // Load config with defaults first, then try to load user and repo configs
+/// Loads the repository and resolves a revision (default @) to its commit
+fn load_commit(
+    revision: Option<&str>,
+) -> anyhow::Result<(
+    std::sync::Arc<jj_lib::repo::ReadonlyRepo>,
+    jj_lib::commit::Commit,
+)> {
+    let config = StackedConfig::with_defaults();
+    let user_settings = UserSettings::from_config(config)?;
+    let store_factories = jj_lib::repo::StoreFactories::default();
+    let working_copy_factories = jj_lib::workspace::default_working_copy_factories();
+
+    let workspace = Workspace::load(
+        &user_settings,
+        Path::new("."),
+        &store_factories,
+        &working_copy_factories,
+    )?;
+    let repo = workspace.repo_loader().load_at_head()?;
+
+    // Resolve revision (default to @)
+    let rev = revision.unwrap_or("@");
+    let commit = if rev == "@" {
+        // Get the working copy commit
+        let wc_commit_id = repo
+            .view()
+            .get_wc_commit_id(workspace.workspace_name())
+            .ok_or_else(|| anyhow::anyhow!("No working copy commit found"))?;
+        repo.store().get_commit(wc_commit_id)?
+    } else {
+        // Resolve revision using jj's index for prefix matching
+        let commit_id = resolve_revision_to_commit_id(&repo, rev)?;
+        repo.store().get_commit(&commit_id)?
+    };
+
+    Ok((repo, commit))
+}
+
+/// Returns up to `count` non-empty descriptions of the ancestors of a revision
+/// (default @), following first parents
+pub fn get_jj_recent_descriptions(
+    revision: Option<&str>,
+    count: usize,
+) -> anyhow::Result<Vec<String>> {
+    let (repo, commit) = load_commit(revision)?;
+
+    let root_id = repo.store().root_commit_id().clone();
+    let mut descriptions = Vec::new();
+    let mut next = commit.parent_ids().first().cloned();
+    while let Some(commit_id) = next {
+        if descriptions.len() >= count || commit_id == root_id {
+            break;
+        }
+        let ancestor = repo.store().get_commit(&commit_id)?;
+        // Skip merges, like the git path does
+        if ancestor.parent_ids().len() == 1 && !ancestor.description().trim().is_empty() {
+            descriptions.push(ancestor.description().trim().to_string());
+        }
+        next = ancestor.parent_ids().first().cloned();
+    }
+    Ok(descriptions)
+}
+
+/// Returns the first local bookmark on a revision (default @), falling back to
+/// its parent since new work usually sits on top of the bookmarked commit
+pub fn get_jj_bookmark(revision: Option<&str>) -> anyhow::Result<Option<String>> {
+    let (repo, commit) = load_commit(revision)?;
+
+    let candidates = std::iter::once(commit.id()).chain(commit.parent_ids().first());
+    for commit_id in candidates {
+        if let Some((name, _)) = repo.view().local_bookmarks_for_commit(commit_id).next() {
+            return Ok(Some(name.as_str().to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Change IDs (shortened) and titles of the working copy and its ancestors,
+/// newest first
+pub fn get_jj_recent_changes(count: usize) -> anyhow::Result<Vec<(String, String)>> {
+    let (repo, commit) = load_commit(None)?;
+
+    let root_id = repo.store().root_commit_id().clone();
+    let mut changes = Vec::new();
+    let mut next = Some(commit.id().clone());
+    while let Some(commit_id) = next {
+        if changes.len() >= count || commit_id == root_id {
+            break;
+        }
+        let commit = repo.store().get_commit(&commit_id)?;
+        let change_id: String = commit.change_id().reverse_hex().chars().take(8).collect();
+        let title = commit
+            .description()
+            .lines()
+            .next()
+            .unwrap_or("")
+            .to_string();
+        changes.push((change_id, title));
+        next = commit.parent_ids().first().cloned();
+    }
+    Ok(changes)
+}
+
+/// Returns up to `count` distinct `Name <email>` authors of the ancestors of a
+/// revision (default @), excluding `exclude`
+pub fn get_jj_recent_authors(
+    revision: Option<&str>,
+    count: usize,
+    exclude: Option<&str>,
+) -> anyhow::Result<Vec<String>> {
+    let (repo, commit) = load_commit(revision)?;
+
+    let root_id = repo.store().root_commit_id().clone();
+    let mut authors: Vec<String> = Vec::new();
+    let mut next = commit.parent_ids().first().cloned();
+    // Look at a bounded window of history so large repos stay fast
+    for _ in 0..500 {
+        let Some(commit_id) = next else { break };
+        if authors.len() >= count || commit_id == root_id {
+            break;
+        }
+        let ancestor = repo.store().get_commit(&commit_id)?;
+        let author = ancestor.author();
+        if !author.name.is_empty() && !author.email.is_empty() {
+            let identity = format!("{} <{}>", author.name, author.email);
+            if Some(identity.as_str()) != exclude && !authors.contains(&identity) {
+                authors.push(identity);
+            }
+        }
+        next = ancestor.parent_ids().first().cloned();
+    }
+    Ok(authors)
+}
+
+/// Returns the configured `Name <email>` identity, if both are set
+pub fn get_jj_user() -> anyhow::Result<Option<String>> {
+    let user_settings = UserSettings::from_config(user_config())?;
+    let name = user_settings.user_name();
+    let email = user_settings.user_email();
+    if name.is_empty() || email.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(format!("{} <{}>", name, email)))
+}
+
+/// Loads config with defaults first, then the user and repo configs if present
+fn user_config() -> StackedConfig {
     let mut config = StackedConfig::with_defaults();
 
     // Try to load user config from standard locations
@@ -614,7 +876,12 @@ pub fn set_jj_description(revision: Option<&str>, description: &str) -> anyhow::
         let _ = config.load_file(jj_lib::config::ConfigSource::Repo, repo_config_path);
     }
 
-    let user_settings = UserSettings::from_config(config)?;
+    config
+}
+
+/// Sets the description for a Jujutsu revision by rewriting the target commit
+pub fn set_jj_description(revision: Option<&str>, description: &str) -> anyhow::Result<()> {
+    let user_settings = UserSettings::from_config(user_config())?;
     let store_factories = jj_lib::repo::StoreFactories::default();
     let working_copy_factories = jj_lib::workspace::default_working_copy_factories();
 
@@ -782,3 +1049,65 @@ pub fn get_jj_modified_files() -> anyhow::Result<Vec<String>> {
     }
     Ok(modified_files)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity(b"a\nb\n", b"a\nb\n"), 100);
+        assert_eq!(similarity(b"a\nb\nc\nd\n", b"a\nb\nc\nx\n"), 75);
+        assert_eq!(similarity(b"a\n", b"b\n"), 0);
+    }
+
+    #[test]
+    fn test_detect_rename_and_copy() {
+        let deleted = vec![("old/lib.rs".to_string(), b"a\nb\nc\nd\n".to_vec())];
+        let added = vec![
+            ("new/lib.rs".to_string(), b"a\nb\nc\nx\n".to_vec()),
+            ("copy.rs".to_string(), b"1\n2\n3\n".to_vec()),
+            ("unrelated.rs".to_string(), b"z\n".to_vec()),
+        ];
+        let modified = vec![("orig.rs".to_string(), b"1\n2\n3\n".to_vec())];
+
+        let pairs = detect_file_pairs(&deleted, &added, &modified);
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[0].kind, PairKind::Rename);
+        assert_eq!(pairs[0].from, "old/lib.rs");
+        assert_eq!(pairs[0].to, "new/lib.rs");
+        assert_eq!(pairs[0].similarity, 75);
+        assert_eq!(pairs[1].kind, PairKind::Copy);
+        assert_eq!(pairs[1].from, "orig.rs");
+    }
+
+    #[test]
+    fn test_pure_rename_has_no_content_delta() {
+        let pair = FilePair {
+            kind: PairKind::Rename,
+            from: "a.rs".to_string(),
+            to: "b.rs".to_string(),
+            similarity: 100,
+        };
+        let output = format_file_pair(&pair, b"x\n", b"x\n", &DiffContext::default()).unwrap();
+        assert_eq!(
+            output,
+            "diff --git a/a.rs b/b.rs\nsimilarity index 100%\nrename from a.rs\nrename to b.rs\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_context_and_symbols() {
+        let source = b"fn a() {\n    one();\n    two();\n    three();\n}\n";
+        let target = b"fn a() {\n    one();\n    deux();\n    three();\n}\n";
+        let context = DiffContext {
+            lines: 1,
+            symbols: true,
+        };
+        let output = format_unified_diff(source, target, &context).unwrap();
+        assert_eq!(
+            output,
+            "@@ -2,3 +2,3 @@ fn a\n     one();\n-    two();\n+    deux();\n     three();\n"
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use crate::convention::ConventionConfig;
+use crate::lint::Violation;
+use crate::openai::CommitSuggestion;
+use crate::trailers;
+
+/// How a language can be recognized in generated text
+enum Detection {
+    /// At least one character must fall into one of the ranges
+    Script(&'static [(char, char)]),
+    /// Its stop words must outnumber the English ones
+    StopWords(&'static [&'static str]),
+}
+
+struct Language {
+    code: &'static str,
+    name: &'static str,
+    detection: Detection,
+}
+
+const CJK: (char, char) = ('\u{4e00}', '\u{9fff}');
+const KANA: (char, char) = ('\u{3040}', '\u{30ff}');
+const HANGUL: (char, char) = ('\u{ac00}', '\u{d7af}');
+const CYRILLIC: (char, char) = ('\u{0400}', '\u{04ff}');
+
+const ENGLISH_STOP_WORDS: &[&str] = &[
+    "the", "and", "to", "of", "for", "with", "this", "that", "when", "from", "by", "it", "are",
+    "be", "instead", "so",
+];
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        code: "de",
+        name: "German",
+        detection: Detection::StopWords(&[
+            "der", "die", "das", "und", "nicht", "mit", "für", "von", "den", "dem", "ein", "eine",
+            "ist", "wird", "beim", "auf", "zu", "damit", "statt",
+        ]),
+    },
+    Language {
+        code: "fr",
+        name: "French",
+        detection: Detection::StopWords(&[
+            "le", "la", "les", "et", "des", "du", "pour", "avec", "est", "dans", "une", "un",
+            "qui", "au", "aux", "sur", "pas",
+        ]),
+    },
+    Language {
+        code: "es",
+        name: "Spanish",
+        detection: Detection::StopWords(&[
+            "el", "la", "los", "las", "y", "de", "del", "para", "con", "que", "en", "una", "por",
+            "se", "al", "no",
+        ]),
+    },
+    Language {
+        code: "it",
+        name: "Italian",
+        detection: Detection::StopWords(&[
+            "il", "lo", "la", "gli", "le", "e", "di", "del", "per", "con", "che", "una", "un",
+            "non", "della", "nel",
+        ]),
+    },
+    Language {
+        code: "pt",
+        name: "Portuguese",
+        detection: Detection::StopWords(&[
+            "o", "os", "as", "e", "de", "do", "da", "para", "com", "que", "uma", "um", "não", "em",
+            "no", "na",
+        ]),
+    },
+    Language {
+        code: "nl",
+        name: "Dutch",
+        detection: Detection::StopWords(&[
+            "de", "het", "een", "en", "van", "voor", "met", "dat", "niet", "op", "bij", "wordt",
+            "om",
+        ]),
+    },
+    Language {
+        code: "pl",
+        name: "Polish",
+        detection: Detection::StopWords(&[
+            "i", "w", "z", "na", "do", "nie", "się", "dla", "jest", "że", "oraz", "przy",
+        ]),
+    },
+    Language {
+        code: "sv",
+        name: "Swedish",
+        detection: Detection::StopWords(&[
+            "och", "att", "det", "för", "med", "som", "en", "ett", "av", "på", "är", "inte",
+        ]),
+    },
+    Language {
+        code: "tr",
+        name: "Turkish",
+        detection: Detection::StopWords(&[
+            "ve", "bir", "bu", "için", "ile", "da", "de", "olarak", "değil", "daha",
+        ]),
+    },
+    Language {
+        code: "ja",
+        name: "Japanese",
+        detection: Detection::Script(&[KANA, CJK]),
+    },
+    Language {
+        code: "zh",
+        name: "Chinese",
+        detection: Detection::Script(&[CJK]),
+    },
+    Language {
+        code: "ko",
+        name: "Korean",
+        detection: Detection::Script(&[HANGUL]),
+    },
+    Language {
+        code: "ru",
+        name: "Russian",
+        detection: Detection::Script(&[CYRILLIC]),
+    },
+    Language {
+        code: "uk",
+        name: "Ukrainian",
+        detection: Detection::Script(&[CYRILLIC]),
+    },
+];
+
+/// Whether the configured language means English, the model's default
+pub fn is_default(language: &str) -> bool {
+    let language = language.trim();
+    language.is_empty()
+        || language.eq_ignore_ascii_case("en")
+        || language.eq_ignore_ascii_case("english")
+}
+
+fn lookup(language: &str) -> Option<&'static Language> {
+    let language = language.trim();
+    LANGUAGES
+        .iter()
+        .find(|l| l.code.eq_ignore_ascii_case(language) || l.name.eq_ignore_ascii_case(language))
+}
+
+/// Display name of a language, e.g. `German` for `de`
+pub fn name(language: &str) -> String {
+    lookup(language)
+        .map(|l| l.name.to_string())
+        .unwrap_or_else(|| language.trim().to_string())
+}
+
+/// The `<language>` section of the system prompt
+pub fn prompt(language: &str) -> Option<String> {
+    if is_default(language) {
+        return None;
+    }
+    let name = name(language);
+    Some(format!(
+        "<language>
+- Write every title description and body in {name}.
+- Keep commit type keywords, scopes, code identifiers, and trailer tokens in English exactly as the commit rules require.
+- Only the text after the type and scope is written in {name}.
+</language>"
+    ))
+}
+
+/// Flags suggestions whose description and body are apparently not written in
+/// the configured language. Unknown languages aren't checked.
+pub fn check(
+    language: &str,
+    convention: &ConventionConfig,
+    suggestion: &CommitSuggestion,
+) -> Option<Violation> {
+    if is_default(language) {
+        return None;
+    }
+    let target = lookup(language)?;
+
+    let title = suggestion.title.trim();
+    let mut text = convention
+        .parse_header(title)
+        .map_or(title, |header| header.subject)
+        .to_string();
+    for line in suggestion.body.as_deref().unwrap_or("").lines() {
+        if trailers::parse(line).is_none() {
+            text.push('\n');
+            text.push_str(line);
+        }
+    }
+
+    let matches = match &target.detection {
+        Detection::Script(ranges) => text.chars().any(|c| {
+            ranges
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&c))
+        }),
+        Detection::StopWords(words) => {
+            let tokens: Vec<String> = text
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|t| !t.is_empty())
+                .map(str::to_lowercase)
+                .collect();
+            let count =
+                |list: &[&str]| tokens.iter().filter(|t| list.contains(&t.as_str())).count();
+            // Short texts don't carry enough stop words to tell
+            tokens.len() < 5 || count(words) >= count(ENGLISH_STOP_WORDS)
+        }
+    };
+
+    (!matches).then(|| Violation {
+        rule: "language",
+        message: format!(
+            "description and body must be written in {} (type keywords stay in English)",
+            target.name
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suggestion(title: &str, body: Option<&str>) -> CommitSuggestion {
+        CommitSuggestion {
+            title: title.to_string(),
+            body: body.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_prompt() {
+        assert_eq!(prompt(""), None);
+        assert_eq!(prompt("English"), None);
+        assert!(prompt("de").unwrap().contains("in German."));
+        assert!(prompt("Klingon").unwrap().contains("in Klingon."));
+    }
+
+    #[test]
+    fn test_check_stop_words() {
+        let convention = ConventionConfig::default();
+        let german = suggestion(
+            "fix(api): Zeitüberschreitung beim Laden der Konfiguration beheben",
+            Some("Die Anfrage wird jetzt mit einem Timeout abgebrochen.\n\nRefs: PROJ-1"),
+        );
+        assert_eq!(check("de", &convention, &german), None);
+
+        let english = suggestion(
+            "fix(api): handle the timeout when loading the config",
+            Some("The request is now cancelled instead of hanging forever."),
+        );
+        assert_eq!(check("de", &convention, &english).unwrap().rule, "language");
+        assert_eq!(check("en", &convention, &english), None);
+        assert_eq!(check("Klingon", &convention, &english), None);
+    }
+
+    #[test]
+    fn test_check_script() {
+        let convention = ConventionConfig::default();
+        let japanese = suggestion("feat(cli): 設定ファイルの読み込みを追加", None);
+        assert_eq!(check("ja", &convention, &japanese), None);
+
+        let english = suggestion("feat(cli): add config loading", None);
+        assert!(check("Japanese", &convention, &english).is_some());
+    }
+}
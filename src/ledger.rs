@@ -0,0 +1,370 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveDate};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::StatsArgs;
+use crate::config::Config;
+use crate::pricing::{self, Pricing};
+use crate::util;
+
+/// Groupings `turbocommit stats --by` accepts
+pub const GROUPS: &[&str] = &["day", "month", "repo", "model"];
+
+/// Where the usage of every request is recorded
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LedgerConfig {
+    pub enabled: bool,
+    /// JSONL file; empty means `~/.turbocommit/usage.jsonl`
+    pub path: String,
+}
+
+impl Default for LedgerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: String::new(),
+        }
+    }
+}
+
+impl LedgerConfig {
+    /// The resolved ledger file
+    pub fn path(&self) -> PathBuf {
+        util::config_path(&self.path, "usage.jsonl")
+    }
+
+    /// Appends entries to the ledger, creating it if needed
+    pub fn append(&self, entries: &[Entry]) -> anyhow::Result<()> {
+        if !self.enabled || entries.is_empty() {
+            return Ok(());
+        }
+        let path = self.path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut lines = String::new();
+        for entry in entries {
+            lines.push_str(&serde_json::to_string(entry)?);
+            lines.push('\n');
+        }
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(lines.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// One API request and how the run it belonged to ended
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Entry {
+    /// RFC 3339 in local time, so days and months group like the user sees them
+    pub timestamp: String,
+    pub model: String,
+    /// Root of the repository, empty for `--diff`
+    pub repo: String,
+    pub prompt_tokens: usize,
+    /// Prompt tokens served from the provider's prompt cache
+    #[serde(default)]
+    pub cached_tokens: usize,
+    pub completion_tokens: usize,
+    #[serde(default)]
+    pub reasoning_tokens: usize,
+    pub duration_ms: u128,
+    /// `committed`, `aborted`, `failed` or `printed`
+    pub outcome: String,
+    /// Cost in USD at the prices configured when the request was made
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+}
+
+impl Entry {
+    /// The recorded cost, or for older entries without one, the cost at the
+    /// current prices of the entry's model
+    pub fn cost(&self, pricing: &Pricing) -> f64 {
+        self.cost_usd.unwrap_or_else(|| {
+            pricing.for_model(&self.model).cost(
+                self.prompt_tokens,
+                self.cached_tokens,
+                self.completion_tokens,
+            )
+        })
+    }
+
+    /// The local day of the request
+    pub fn date(&self) -> Option<NaiveDate> {
+        DateTime::parse_from_rfc3339(&self.timestamp)
+            .ok()
+            .map(|time| time.date_naive())
+    }
+
+    /// The value of the `stats --by` grouping for this entry
+    fn group(&self, by: &str) -> String {
+        match by {
+            "month" => self.timestamp.get(..7).unwrap_or_default().to_string(),
+            "repo" if self.repo.is_empty() => "(no repository)".to_string(),
+            "repo" => self.repo.clone(),
+            "model" => self.model.clone(),
+            _ => self.timestamp.get(..10).unwrap_or_default().to_string(),
+        }
+    }
+}
+
+/// The current time as recorded in the ledger
+pub fn timestamp() -> String {
+    Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+}
+
+/// Reads the ledger, skipping lines that aren't entries
+pub fn read(path: &Path) -> anyhow::Result<Vec<Entry>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Sums of the entries in one group
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Totals {
+    pub requests: usize,
+    pub committed: usize,
+    pub prompt_tokens: usize,
+    pub cached_tokens: usize,
+    pub completion_tokens: usize,
+    pub reasoning_tokens: usize,
+    pub cost: f64,
+}
+
+impl Totals {
+    fn add(&mut self, entry: &Entry, pricing: &Pricing) {
+        self.requests += 1;
+        self.committed += usize::from(entry.outcome == "committed");
+        self.prompt_tokens += entry.prompt_tokens;
+        self.cached_tokens += entry.cached_tokens;
+        self.completion_tokens += entry.completion_tokens;
+        self.reasoning_tokens += entry.reasoning_tokens;
+        self.cost += entry.cost(pricing);
+    }
+}
+
+/// Parses `YYYY-MM-DD`, or `YYYY-MM` for the first (or with `end`, last) day of the month
+fn parse_date(date: &str, end: bool) -> anyhow::Result<NaiveDate> {
+    if let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        return Ok(date);
+    }
+    let first = NaiveDate::parse_from_str(&format!("{}-01", date), "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("Invalid date '{}', expected YYYY-MM-DD or YYYY-MM", date))?;
+    Ok(match end {
+        true => first
+            .checked_add_months(chrono::Months::new(1))
+            .and_then(|next| next.pred_opt())
+            .unwrap_or(first),
+        false => first,
+    })
+}
+
+/// Totals per group for the entries between `since` and `until`, both inclusive
+pub fn summarize(
+    entries: &[Entry],
+    by: &str,
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    pricing: &Pricing,
+) -> BTreeMap<String, Totals> {
+    let mut groups: BTreeMap<String, Totals> = BTreeMap::new();
+    for entry in entries {
+        let date = entry.date();
+        if since.is_some_and(|since| date.is_none_or(|date| date < since))
+            || until.is_some_and(|until| date.is_none_or(|date| date > until))
+        {
+            continue;
+        }
+        groups
+            .entry(entry.group(by))
+            .or_default()
+            .add(entry, pricing);
+    }
+    groups
+}
+
+/// `turbocommit stats`
+pub fn print_stats(args: &StatsArgs, config: &Config) -> anyhow::Result<()> {
+    let since = args
+        .since
+        .as_deref()
+        .map(|date| parse_date(date, false))
+        .transpose()?;
+    let until = args
+        .until
+        .as_deref()
+        .map(|date| parse_date(date, true))
+        .transpose()?;
+    let path = config.ledger.path();
+    let groups = summarize(&read(&path)?, &args.by, since, until, &config.pricing);
+    if groups.is_empty() {
+        println!(
+            "{} {}",
+            "No usage recorded in".bright_black(),
+            path.display()
+        );
+        return Ok(());
+    }
+
+    let mut total = Totals::default();
+    for totals in groups.values() {
+        total.requests += totals.requests;
+        total.committed += totals.committed;
+        total.prompt_tokens += totals.prompt_tokens;
+        total.cached_tokens += totals.cached_tokens;
+        total.completion_tokens += totals.completion_tokens;
+        total.reasoning_tokens += totals.reasoning_tokens;
+        total.cost += totals.cost;
+    }
+
+    let width = groups
+        .keys()
+        .map(|key| key.chars().count())
+        .chain([args.by.len(), "Total".len()])
+        .max()
+        .unwrap_or_default();
+    let row = |key: &str, totals: &Totals| {
+        format!(
+            "{:<width$}  {:>8}  {:>9}  {:>9}  {:>9}  {:>9}  {:>9}  {:>9}",
+            key,
+            totals.requests,
+            totals.committed,
+            util::format_token_count(totals.prompt_tokens),
+            util::format_token_count(totals.cached_tokens),
+            util::format_token_count(totals.completion_tokens),
+            util::format_token_count(totals.reasoning_tokens),
+            pricing::format_cost(totals.cost),
+        )
+    };
+    println!(
+        "{}",
+        format!(
+            "{:<width$}  {:>8}  {:>9}  {:>9}  {:>9}  {:>9}  {:>9}  {:>9}",
+            args.by, "Requests", "Committed", "Input", "Cached", "Output", "Reasoning", "Cost"
+        )
+        .bold()
+    );
+    for (key, totals) in &groups {
+        println!("{}", row(key, totals));
+    }
+    println!("{}", row("Total", &total).purple());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn entry(timestamp: &str, repo: &str, prompt_tokens: usize, outcome: &str) -> Entry {
+        Entry {
+            timestamp: timestamp.to_string(),
+            model: "gpt-5.4".to_string(),
+            repo: repo.to_string(),
+            prompt_tokens,
+            cached_tokens: 0,
+            completion_tokens: 100,
+            reasoning_tokens: 20,
+            duration_ms: 900,
+            outcome: outcome.to_string(),
+            cost_usd: None,
+        }
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let dir = tempdir().unwrap();
+        let config = LedgerConfig {
+            enabled: true,
+            path: dir.path().join("usage.jsonl").to_string_lossy().to_string(),
+        };
+        assert!(read(&config.path()).unwrap().is_empty());
+
+        let first = entry("2026-09-30T10:00:00+02:00", "/src/api", 1000, "committed");
+        let second = entry("2026-10-01T10:00:00+02:00", "", 2000, "aborted");
+        config.append(std::slice::from_ref(&first)).unwrap();
+        config.append(std::slice::from_ref(&second)).unwrap();
+        assert_eq!(read(&config.path()).unwrap(), vec![first, second]);
+
+        let disabled = LedgerConfig {
+            enabled: false,
+            path: dir.path().join("off.jsonl").to_string_lossy().to_string(),
+        };
+        disabled
+            .append(&[entry("2026-10-01T10:00:00+02:00", "", 1, "aborted")])
+            .unwrap();
+        assert!(!disabled.path().exists());
+    }
+
+    #[test]
+    fn test_summarize() {
+        let entries = vec![
+            entry(
+                "2026-09-30T10:00:00+02:00",
+                "/src/api",
+                1_000_000,
+                "committed",
+            ),
+            entry(
+                "2026-10-01T09:00:00+02:00",
+                "/src/api",
+                1_000_000,
+                "aborted",
+            ),
+            entry("2026-10-01T18:00:00+02:00", "", 1_000_000, "committed"),
+        ];
+        let pricing = Pricing::default();
+
+        let by_day = summarize(&entries, "day", None, None, &pricing);
+        assert_eq!(
+            by_day.keys().collect::<Vec<_>>(),
+            vec!["2026-09-30", "2026-10-01"]
+        );
+        let day = &by_day["2026-10-01"];
+        assert_eq!((day.requests, day.committed), (2, 1));
+        assert_eq!(day.cost, 2.0 * (2.5 + 100.0 * 15.0 / 1_000_000.0));
+
+        // Recorded costs are kept even when prices change later
+        let recorded = Entry {
+            cost_usd: Some(0.5),
+            ..entries[0].clone()
+        };
+        let by_day = summarize(&[recorded], "day", None, None, &pricing);
+        assert_eq!(by_day["2026-09-30"].cost, 0.5);
+
+        let by_repo = summarize(&entries, "repo", None, None, &pricing);
+        assert_eq!(by_repo["/src/api"].requests, 2);
+        assert_eq!(by_repo["(no repository)"].requests, 1);
+
+        let october = summarize(
+            &entries,
+            "month",
+            Some(parse_date("2026-10", false).unwrap()),
+            Some(parse_date("2026-10", true).unwrap()),
+            &pricing,
+        );
+        assert_eq!(october.keys().collect::<Vec<_>>(), vec!["2026-10"]);
+        assert_eq!(october["2026-10"].requests, 2);
+
+        assert_eq!(
+            parse_date("2026-02", true).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 2, 28).unwrap()
+        );
+        assert!(parse_date("last week", false).is_err());
+    }
+}
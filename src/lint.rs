@@ -0,0 +1,468 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::convention::{self, ConventionConfig};
+use crate::language;
+use crate::openai::CommitSuggestion;
+
+/// commitlint-style rules applied to every suggestion
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LintConfig {
+    pub enabled: bool,
+    /// Allowed commit types; empty uses the convention preset's types
+    pub types: Vec<String>,
+    /// Maximum header length in characters (0 disables the check); unset uses
+    /// the convention preset's limit
+    pub header_max_length: Option<usize>,
+    /// Reject headers ending with a period
+    pub header_full_stop: bool,
+    /// Maximum body line length; longer lines are re-wrapped (0 disables the check)
+    pub body_max_line_length: usize,
+    /// Footer tokens that must be present, e.g. `Refs` or `Signed-off-by`
+    pub footers_required: Vec<String>,
+    /// How often violations that can't be fixed locally are sent back to the model
+    pub max_repair_rounds: usize,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            types: Vec::new(),
+            header_max_length: None,
+            header_full_stop: true,
+            body_max_line_length: 0,
+            footers_required: Vec::new(),
+            max_repair_rounds: 1,
+        }
+    }
+}
+
+/// A rule a suggestion still violates after local repairs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.rule, self.message)
+    }
+}
+
+/// The parts of a conventional commit header
+#[derive(Debug, PartialEq)]
+pub struct Header<'a> {
+    pub kind: &'a str,
+    pub scope: Option<&'a str>,
+    pub breaking: bool,
+    pub subject: &'a str,
+    /// Byte offset of the subject in the title
+    pub subject_start: usize,
+}
+
+/// Parses `type(scope)!: subject`
+pub fn parse_header(title: &str) -> Option<Header<'_>> {
+    let (prefix, subject) = title.split_once(": ")?;
+    let (prefix, breaking) = match prefix.strip_suffix('!') {
+        Some(prefix) => (prefix, true),
+        None => (prefix, false),
+    };
+    let (kind, scope) = match prefix.split_once('(') {
+        Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?)),
+        None => (prefix, None),
+    };
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    if scope.is_some_and(|s| s.is_empty() || s.contains(['(', ')'])) {
+        return None;
+    }
+    Some(Header {
+        kind,
+        scope,
+        breaking,
+        subject: subject.trim(),
+        subject_start: title.len() - subject.trim_start().len(),
+    })
+}
+
+/// Whether a body line is a trailer like `Refs: #12` or `Closes #34`
+fn is_footer_line(line: &str) -> bool {
+    if line.starts_with("BREAKING CHANGE: ") || line.starts_with("BREAKING-CHANGE: ") {
+        return true;
+    }
+    let token_end = line
+        .find(": ")
+        .or_else(|| line.find(" #"))
+        .unwrap_or(line.len());
+    token_end < line.len()
+        && token_end > 0
+        && line[..token_end]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn has_footer(body: &str, token: &str) -> bool {
+    body.lines().any(|line| {
+        line.strip_prefix(token)
+            .is_some_and(|rest| rest.starts_with(": ") || rest.starts_with(" #"))
+    })
+}
+
+/// Greedily wraps a line at word boundaries
+fn wrap_line(line: &str, width: usize) -> String {
+    let mut wrapped = String::new();
+    let mut current = 0;
+    for word in line.split_whitespace() {
+        let len = word.chars().count();
+        if current > 0 && current + 1 + len > width {
+            wrapped.push('\n');
+            current = 0;
+        } else if current > 0 {
+            wrapped.push(' ');
+            current += 1;
+        }
+        wrapped.push_str(word);
+        current += len;
+    }
+    wrapped
+}
+
+impl LintConfig {
+    /// Checks the output language, which is a lint rule like the others and
+    /// off with them
+    pub fn check_language(
+        &self,
+        language: &str,
+        convention: &ConventionConfig,
+        suggestion: &CommitSuggestion,
+    ) -> Option<Violation> {
+        if !self.enabled {
+            return None;
+        }
+        language::check(language, convention, suggestion)
+    }
+
+    /// Applies the fixes that don't need the model (type case, trailing period,
+    /// body wrapping) and returns the violations that remain
+    pub fn repair(
+        &self,
+        convention: &ConventionConfig,
+        suggestion: &mut CommitSuggestion,
+    ) -> Vec<Violation> {
+        if !self.enabled {
+            return Vec::new();
+        }
+
+        suggestion.title = suggestion.title.trim().to_string();
+        if self.header_full_stop {
+            while suggestion.title.ends_with('.') {
+                suggestion.title.pop();
+            }
+        }
+        let types = self.types(convention);
+        if let Some(header) = convention.parse_header(&suggestion.title) {
+            let lower = header.kind.to_lowercase();
+            if lower != header.kind && types.contains(&lower) {
+                suggestion.title = suggestion.title.replacen(header.kind, &lower, 1);
+            }
+        }
+
+        if self.body_max_line_length > 0 {
+            if let Some(body) = suggestion.body.as_mut() {
+                *body = body
+                    .lines()
+                    .map(|line| {
+                        if line.chars().count() > self.body_max_line_length && !is_footer_line(line)
+                        {
+                            wrap_line(line, self.body_max_line_length)
+                        } else {
+                            line.to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+            }
+        }
+
+        self.check(convention, suggestion)
+    }
+
+    /// The configured types, falling back to the convention's
+    fn types(&self, convention: &ConventionConfig) -> Vec<String> {
+        if self.types.is_empty() {
+            convention.types()
+        } else {
+            self.types.clone()
+        }
+    }
+
+    /// Checks a suggestion against every rule without modifying it
+    pub fn check(
+        &self,
+        convention: &ConventionConfig,
+        suggestion: &CommitSuggestion,
+    ) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        if !self.enabled {
+            return violations;
+        }
+        let title = suggestion.title.trim();
+
+        let types = self.types(convention);
+        match convention.parse_header(title) {
+            None => violations.push(Violation {
+                rule: "header-format",
+                message: format!("header '{}' must match `{}`", title, convention.shape()),
+            }),
+            Some(header) => {
+                if !types.is_empty() && !types.iter().any(|t| convention::same_kind(t, header.kind))
+                {
+                    violations.push(Violation {
+                        rule: "type-enum",
+                        message: format!(
+                            "type '{}' must be one of: {}",
+                            header.kind,
+                            types.join(", ")
+                        ),
+                    });
+                }
+                if header.subject.is_empty() {
+                    violations.push(Violation {
+                        rule: "subject-empty",
+                        message: "subject may not be empty".to_string(),
+                    });
+                }
+            }
+        }
+
+        let header_len = title.chars().count();
+        let max_length = self
+            .header_max_length
+            .unwrap_or_else(|| convention.header_max_length());
+        if max_length > 0 && header_len > max_length {
+            violations.push(Violation {
+                rule: "header-max-length",
+                message: format!(
+                    "header is {} characters, maximum is {}",
+                    header_len, max_length
+                ),
+            });
+        }
+
+        if self.header_full_stop && title.ends_with('.') {
+            violations.push(Violation {
+                rule: "header-full-stop",
+                message: "header may not end with a period".to_string(),
+            });
+        }
+
+        let body = suggestion.body.as_deref().unwrap_or("");
+        if self.body_max_line_length > 0 {
+            if let Some(line) = body.lines().find(|line| {
+                line.chars().count() > self.body_max_line_length
+                    && !is_footer_line(line)
+                    && line.contains(' ')
+            }) {
+                violations.push(Violation {
+                    rule: "body-max-line-length",
+                    message: format!(
+                        "body line '{}' is longer than {} characters",
+                        line, self.body_max_line_length
+                    ),
+                });
+            }
+        }
+
+        for token in &self.footers_required {
+            if !has_footer(body, token) {
+                violations.push(Violation {
+                    rule: "footer-required",
+                    message: format!("a `{}:` footer is required", token),
+                });
+            }
+        }
+
+        violations
+    }
+
+    /// Builds the corrective user message listing violations per suggestion index
+    pub fn correction_prompt(violations: &[(usize, Vec<Violation>)]) -> String {
+        let mut prompt = String::from(
+            "Some suggestions violate this repository's commit message rules. \
+             Return all suggestions again, fixing these violations and keeping the rest unchanged:\n",
+        );
+        for (index, list) in violations {
+            for violation in list {
+                prompt.push_str(&format!("- suggestion {}: {}\n", index, violation));
+            }
+        }
+        prompt.trim_end().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suggestion(title: &str, body: Option<&str>) -> CommitSuggestion {
+        CommitSuggestion {
+            title: title.to_string(),
+            body: body.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("feat(api)!: add endpoint"),
+            Some(Header {
+                kind: "feat",
+                scope: Some("api"),
+                breaking: true,
+                subject: "add endpoint",
+                subject_start: 12,
+            })
+        );
+        assert!(parse_header("fix: bug").is_some());
+        assert!(parse_header("fix:bug").is_none());
+        assert!(parse_header("fix(): bug").is_none());
+        assert!(parse_header("Update the readme").is_none());
+    }
+
+    #[test]
+    fn test_repair_fixes_type_case_and_full_stop() {
+        let config = LintConfig::default();
+        let mut s = suggestion("Feat(cli): add flag.", None);
+        assert!(config
+            .repair(&ConventionConfig::default(), &mut s)
+            .is_empty());
+        assert_eq!(s.title, "feat(cli): add flag");
+    }
+
+    #[test]
+    fn test_repair_wraps_body() {
+        let config = LintConfig {
+            body_max_line_length: 20,
+            ..LintConfig::default()
+        };
+        let mut s = suggestion(
+            "fix: bug",
+            Some("this body line is definitely too long\n\nRefs: https://example.com/a/very/long/url"),
+        );
+        assert!(config
+            .repair(&ConventionConfig::default(), &mut s)
+            .is_empty());
+        assert_eq!(
+            s.body.as_deref(),
+            Some("this body line is\ndefinitely too long\n\nRefs: https://example.com/a/very/long/url")
+        );
+    }
+
+    #[test]
+    fn test_unfixable_violations() {
+        let config = LintConfig {
+            header_max_length: Some(20),
+            footers_required: vec!["Refs".to_string()],
+            ..LintConfig::default()
+        };
+        let mut s = suggestion("perf: make everything much faster", None);
+        let rules: Vec<&str> = config
+            .repair(&ConventionConfig::default(), &mut s)
+            .iter()
+            .map(|v| v.rule)
+            .collect();
+        assert_eq!(
+            rules,
+            vec!["type-enum", "header-max-length", "footer-required"]
+        );
+
+        let mut s = suggestion("fix: bug", Some("Why.\n\nRefs #12"));
+        assert!(config
+            .repair(&ConventionConfig::default(), &mut s)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_types_follow_convention() {
+        let config = LintConfig::default();
+        let gitmoji = ConventionConfig {
+            preset: "gitmoji".to_string(),
+            ..ConventionConfig::default()
+        };
+        let mut s = suggestion("♻ Split the parser", None);
+        assert!(config.repair(&gitmoji, &mut s).is_empty());
+
+        let mut s = suggestion("feat: split the parser", None);
+        let rules: Vec<&str> = config
+            .repair(&gitmoji, &mut s)
+            .iter()
+            .map(|v| v.rule)
+            .collect();
+        assert_eq!(rules, vec!["header-format"]);
+    }
+
+    #[test]
+    fn test_header_max_length_follows_convention() {
+        let kernel = ConventionConfig {
+            preset: "kernel".to_string(),
+            ..ConventionConfig::default()
+        };
+        let title = format!("net: {}", "a".repeat(75));
+        let rules = |config: &LintConfig, convention: &ConventionConfig| -> Vec<&str> {
+            config
+                .repair(convention, &mut suggestion(&title, None))
+                .iter()
+                .map(|v| v.rule)
+                .collect()
+        };
+
+        let config = LintConfig::default();
+        assert_eq!(rules(&config, &kernel), vec!["header-max-length"]);
+        assert!(rules(&config, &ConventionConfig::default())
+            .iter()
+            .all(|rule| *rule != "header-max-length"));
+
+        // An explicit limit wins over the preset's
+        let config = LintConfig {
+            header_max_length: Some(100),
+            ..LintConfig::default()
+        };
+        assert!(rules(&config, &kernel).is_empty());
+    }
+
+    #[test]
+    fn test_disabled_config_passes_everything() {
+        let config = LintConfig {
+            enabled: false,
+            ..LintConfig::default()
+        };
+        let mut s = suggestion("whatever.", None);
+        assert!(config
+            .repair(&ConventionConfig::default(), &mut s)
+            .is_empty());
+        assert!(config
+            .check_language("ja", &ConventionConfig::default(), &s)
+            .is_none());
+        assert!(LintConfig::default()
+            .check_language("ja", &ConventionConfig::default(), &s)
+            .is_some());
+        assert_eq!(s.title, "whatever.");
+    }
+
+    #[test]
+    fn test_correction_prompt() {
+        let prompt = LintConfig::correction_prompt(&[(
+            1,
+            vec![Violation {
+                rule: "type-enum",
+                message: "type 'perf' must be one of: feat".to_string(),
+            }],
+        )]);
+        assert!(prompt.ends_with("- suggestion 1: type-enum: type 'perf' must be one of: feat"));
+    }
+}
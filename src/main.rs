@@ -1,27 +1,96 @@
 use actor::Actor;
+use clap::{CommandFactory, Parser};
 use colored::Colorize;
 use config::Config;
 
 use openai::Message;
+use output::status;
 
-use std::{env, process, time::Duration};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process,
+};
 
 mod actor;
+mod budget;
+mod cache;
 mod cli;
+mod completions;
 mod config;
+mod config_command;
+mod convention;
 mod debug_log;
+mod diff_context;
 mod git;
+mod hook;
 mod jj;
+mod language;
+mod ledger;
+mod lint;
+mod migrate;
 mod model;
 mod openai;
+mod output;
+mod patch;
+mod pr;
+mod pricing;
+mod profiles;
+mod scopes;
+mod session;
 mod spinner;
+mod style;
+mod template;
+mod tickets;
+mod trailers;
 mod util;
 
+fn options_or_exit(cli: &cli::Cli, config: &Config) -> cli::Options {
+    cli::Options::new(cli, config).unwrap_or_else(|err| {
+        status!("{}", err.to_string().red());
+        process::exit(1);
+    })
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // Answer completion requests from the shell scripts before anything else
+    clap_complete::CompleteEnv::with_factory(cli::Cli::command)
+        .var(completions::COMPLETE_VAR)
+        .complete();
+
+    let cli = cli::Cli::parse();
+    let hook_run = match &cli.command {
+        Some(cli::Command::Hook(cli::HookArgs {
+            action: cli::HookAction::Run { file, source, .. },
+        })) => Some((file, source)),
+        _ => None,
+    };
+    // Git hooks can't be answered and pr prints its draft, so they run like `--format`
+    output::set_machine_readable(
+        cli.commit_args().format.is_some()
+            || hook_run.is_some()
+            || matches!(cli.command, Some(cli::Command::Pr(_))),
+    );
+    match &cli.command {
+        // `turbocommit config ...` works on the config files themselves
+        Some(cli::Command::Config(args)) => config_command::run(args),
+        Some(cli::Command::Hook(args)) => hook::run(args),
+        Some(cli::Command::Completions { shell }) => {
+            return completions::print_completions(shell);
+        }
+        Some(cli::Command::Man { dir }) => return completions::print_man(dir.as_deref()),
+        _ => {}
+    }
+    if let Some((file, source)) = hook_run {
+        if !hook::wants_message(file, source.as_deref()) {
+            return Ok(());
+        }
+    }
+
     // First get the default config just to parse CLI options
     let default_config = Config::load()?;
-    let mut options = cli::Options::new(env::args(), &default_config);
+    let mut options = options_or_exit(&cli, &default_config);
 
     // If check_version_only is set, just check version and exit
     if options.check_version_only {
@@ -34,7 +103,7 @@ async fn main() -> anyhow::Result<()> {
         match Config::load_from_path(std::path::Path::new(config_path)) {
             Ok(config) => config,
             Err(err) => {
-                println!("{}", err.to_string().red());
+                status!("{}", err.to_string().red());
                 process::exit(1);
             }
         }
@@ -42,11 +111,26 @@ async fn main() -> anyhow::Result<()> {
         default_config
     };
 
+    // Layer the repository config, profile and environment over it
+    let config = match config.resolve(options.profile.as_deref()) {
+        Ok(config) => config,
+        Err(err) => {
+            status!("{}", err.to_string().red());
+            process::exit(1);
+        }
+    };
+
+    if let Some(cli::Command::Stats(args)) = &cli.command {
+        return ledger::print_stats(args, &config);
+    }
+
     // Update options with the final config values
-    options = cli::Options::new(env::args(), &config);
+    options = options_or_exit(&cli, &config);
 
     let api_key = match &options.api_key {
         Some(ref key) => key.clone(),
+        // Nothing is sent in a dry run
+        None if options.dry_run => String::new(),
         None => {
             let env_var = &config.api_key_env_var;
             if env_var.trim().is_empty() {
@@ -57,10 +141,10 @@ async fn main() -> anyhow::Result<()> {
                 match env::var(env_var) {
                     Ok(key) => key,
                     Err(_) => {
-                        println!("{}", format!("No API key found. Either:").red());
-                        println!("  1. Set the {} environment variable", env_var.purple());
-                        println!("  2. Use the {} option", "--api-key <key>".purple());
-                        println!("\n{}", "For API key safety best practices, see: https://help.openai.com/en/articles/5112595-best-practices-for-api-key-safety".bright_black());
+                        status!("{}", "No API key found. Either:".red());
+                        status!("  1. Set the {} environment variable", env_var.purple());
+                        status!("  2. Use the {} option", "--api-key <key>".purple());
+                        status!("\n{}", "For API key safety best practices, see: https://help.openai.com/en/articles/5112595-best-practices-for-api-key-safety".bright_black());
                         process::exit(1);
                     }
                 }
@@ -68,13 +152,74 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
-    // Detect VCS type
-    let vcs_type = jj::detect_vcs()?;
+    if let Some(cli::Command::Pr(args)) = &cli.command {
+        return pr::run(args.base.as_deref(), options, api_key).await;
+    }
+
+    // Detect VCS type, a diff given with --diff doesn't need a repository
+    let vcs_type = match (&options.diff, &options.message_file) {
+        (Some(_), _) => jj::VcsType::Patch,
+        // The hook is run by git, also in repositories colocated with jj
+        (None, Some(_)) => jj::VcsType::Git,
+        (None, None) => jj::detect_vcs()?,
+    };
+    let patch = match &options.diff {
+        Some(source) => patch::read(source).unwrap_or_else(|err| {
+            status!("{}", err.to_string().red());
+            process::exit(1);
+        }),
+        None => String::new(),
+    };
+    let patch_files = patch::files(&patch);
 
     // Print which VCS is being used
     match vcs_type {
-        jj::VcsType::Git => println!("{}", "Using Git repository".bright_black()),
-        jj::VcsType::Jujutsu => println!("{}", "Using Jujutsu repository".bright_black()),
+        jj::VcsType::Git => status!("{}", "Using Git repository".bright_black()),
+        jj::VcsType::Jujutsu => status!("{}", "Using Jujutsu repository".bright_black()),
+        jj::VcsType::Patch => status!(
+            "{}",
+            match options.diff.as_deref() {
+                Some("-") | None => "Using diff from stdin".to_string(),
+                Some(file) => format!("Using diff from {}", file),
+            }
+            .bright_black()
+        ),
+    }
+
+    // Continue a saved Commit/Edit/Revise loop instead of starting over
+    if options.resume {
+        let session = match session::Session::path(&vcs_type) {
+            Some(path) => session::Session::load_from(&path)?,
+            None => None,
+        };
+        let Some(session) = session else {
+            status!("{}", "No unfinished session to resume.".red());
+            process::exit(1);
+        };
+        options.amend = session.amend;
+        options.jj_revision = session.jj_revision.clone();
+        // The message must still describe what would be committed
+        let diff_hash =
+            session::diff_hash(&vcs_type, options.amend, options.jj_revision.as_deref())?;
+        if !session.diff_hash.is_empty() && session.diff_hash != diff_hash {
+            status!(
+                "{}",
+                "The changes differ from when the saved message was written.".red()
+            );
+            status!(
+                "{}",
+                "Restore them to resume, or run turbocommit without --resume to start over."
+                    .bright_black()
+            );
+            process::exit(1);
+        }
+        let mut actor = Actor::new(
+            options.clone(),
+            api_key,
+            options.api_endpoint.clone(),
+            vcs_type,
+        );
+        return actor.resume(session).await;
     }
 
     let mut actor = Actor::new(
@@ -84,14 +229,171 @@ async fn main() -> anyhow::Result<()> {
         vcs_type.clone(),
     );
 
-    let system_len =
-        openai::count_token(options.system_msg.as_ref().unwrap_or(&config.system_msg)).unwrap_or(0);
-    let extra_len = openai::count_token(&options.msg).unwrap_or(0);
+    // The branch or bookmark name, for ticket IDs and prompt templates
+    let branch = match vcs_type {
+        jj::VcsType::Git => git::current_branch(&git::get_repo()?),
+        jj::VcsType::Jujutsu => jj::get_jj_bookmark(options.jj_revision.as_deref())?,
+        jj::VcsType::Patch => None,
+    };
+    let ticket_ids = branch
+        .as_deref()
+        .map(|branch| options.tickets.extract(branch))
+        .unwrap_or_default();
+
+    // Fill in the prompt templates, gathering their variables only when needed
+    let system_msg = options
+        .system_msg
+        .clone()
+        .unwrap_or(config.system_msg.clone());
+    let instruction = (!options.msg.is_empty()).then_some(&options.prompts.instruction);
+    let current_description = options
+        .jj_rewrite
+        .then_some(&options.prompts.current_description);
+    let mut variables = template::Variables::new();
+    if [Some(&system_msg), instruction, current_description]
+        .into_iter()
+        .flatten()
+        .any(|text| template::is_template(text))
+    {
+        variables = util::template_variables(
+            &options,
+            &vcs_type,
+            branch.as_deref(),
+            &ticket_ids,
+            &patch_files,
+        )?;
+    }
+    let prompts_dir = options.prompts.dir();
+    let render = |text: &str, variables: &template::Variables| {
+        template::render(text, variables, &prompts_dir).unwrap_or_else(|err| {
+            status!("{}", err.to_string().red());
+            process::exit(1);
+        })
+    };
+
+    // Fill in the convention's rules and the output language
+    let mut system_msg = options
+        .convention
+        .with_rules(&render(&system_msg, &variables));
+    if let Some(language) = language::prompt(&options.language) {
+        system_msg = format!("{}\n\n{}", system_msg, language);
+    }
+    let system_len = openai::count_token(&system_msg).unwrap_or(0);
+    let extra_msg = match instruction {
+        Some(instruction) => {
+            let mut variables = variables.clone();
+            variables.insert("message", options.msg.clone());
+            render(instruction, &variables)
+        }
+        None => String::new(),
+    };
+    let extra_len = openai::count_token(&extra_msg).unwrap_or(0);
+    let diff_context = options.diff_context();
 
     // Add system message first
-    actor.add_message(Message::system(
-        options.system_msg.unwrap_or(config.system_msg.clone()),
-    ));
+    actor.add_message(Message::system(system_msg));
+
+    // Add recent commit messages as style examples
+    let mut style_len = 0;
+    if options.style_examples > 0 {
+        let messages = match vcs_type {
+            jj::VcsType::Git => git::recent_commit_messages(
+                &git::get_repo()?,
+                options.style_examples,
+                // Don't show the commit being amended as an example of itself
+                usize::from(options.amend),
+            )?,
+            jj::VcsType::Jujutsu => jj::get_jj_recent_descriptions(
+                options.jj_revision.as_deref(),
+                options.style_examples,
+            )?,
+            jj::VcsType::Patch => Vec::new(),
+        };
+        if let Some(hint) = style::style_hint(&messages) {
+            style_len = openai::count_token(&hint).unwrap_or(0);
+            actor.add_message(Message::user(hint));
+        }
+    }
+
+    // Add scopes derived from the workspace layout
+    let mut scope_len = 0;
+    if options.scope_inference != "off" {
+        let (root, files) = match vcs_type {
+            jj::VcsType::Git => {
+                let repo = git::get_repo()?;
+                let root = repo.workdir().map(Path::to_path_buf).unwrap_or_default();
+                let files = if options.amend {
+                    git::last_commit_files(&repo)?
+                } else {
+                    git::staged_files(&repo)?
+                };
+                (root, files)
+            }
+            // Modified files are relative to the workspace root, not the cwd
+            jj::VcsType::Jujutsu => (
+                jj::repo_root().unwrap_or_else(|| PathBuf::from(".")),
+                jj::get_jj_modified_files()?,
+            ),
+            jj::VcsType::Patch => (PathBuf::from("."), patch_files.clone()),
+        };
+        let roots = scopes::detect(&root, &config.scope_map);
+        let touched = scopes::touched_scopes(&roots, &files);
+        if let Some(hint) =
+            scopes::scope_hint(&roots, &touched, options.scope_inference == "strict")
+        {
+            scope_len = openai::count_token(&hint).unwrap_or(0);
+            actor.add_message(Message::user(hint));
+        }
+    }
+
+    // Extract ticket IDs from the branch or bookmark name
+    let mut ticket_len = 0;
+    if options.tickets.mode != "off" {
+        if let Some(branch) = &branch {
+            if let Some(hint) = options.tickets.hint(branch, &ticket_ids) {
+                ticket_len = openai::count_token(&hint).unwrap_or(0);
+                actor.add_message(Message::user(hint));
+            }
+            actor.tickets = ticket_ids;
+        }
+    }
+
+    // Collect trailers appended to the chosen message
+    let mut message_trailers = options.trailers.clone();
+    match vcs_type {
+        jj::VcsType::Git => {
+            let repo = git::get_repo()?;
+            if options.pick_co_authors {
+                let authors = util::choose_co_authors(git::recent_authors(&repo, 20)?)?;
+                message_trailers.extend(authors.iter().map(|a| trailers::co_author(a)));
+            }
+            if options.signoff || git::signoff_enabled(&repo) {
+                if let Some(identity) = git::user_identity(&repo) {
+                    message_trailers.push(trailers::sign_off(&identity));
+                }
+            }
+        }
+        jj::VcsType::Jujutsu => {
+            let identity = jj::get_jj_user()?;
+            if options.pick_co_authors {
+                let authors = util::choose_co_authors(jj::get_jj_recent_authors(
+                    options.jj_revision.as_deref(),
+                    20,
+                    identity.as_deref(),
+                )?)?;
+                message_trailers.extend(authors.iter().map(|a| trailers::co_author(a)));
+            }
+            if options.signoff {
+                if let Some(identity) = identity {
+                    message_trailers.push(trailers::sign_off(&identity));
+                }
+            }
+        }
+        // Without a repository there is no identity to sign off with
+        jj::VcsType::Patch => {}
+    }
+    actor.trailers = message_trailers;
+    let used_len = system_len + extra_len + style_len + scope_len + ticket_len;
 
     // Handle different VCS types
     match vcs_type {
@@ -102,26 +404,26 @@ async fn main() -> anyhow::Result<()> {
             if options.amend {
                 // When amending, we don't want any staged files
                 if git::has_staged_changes(&repo)? {
-                    println!("{}", "Error: You have staged changes.".red());
-                    println!(
+                    status!("{}", "Error: You have staged changes.".red());
+                    status!(
                         "{}",
                         "When using --amend, you should not have any staged changes."
                             .bright_black()
                     );
-                    println!(
+                    status!(
                         "{}",
                         "The --amend option only changes the commit message of the last commit."
                             .bright_black()
                     );
-                    println!(
+                    status!(
                         "{}",
                         "If you want to include new changes, either:".bright_black()
                     );
-                    println!(
+                    status!(
                         "{}",
                         "1. Commit them first normally, then amend that commit".bright_black()
                     );
-                    println!(
+                    status!(
                         "{}",
                         "2. Or use git commit --amend manually to include them".bright_black()
                     );
@@ -129,30 +431,32 @@ async fn main() -> anyhow::Result<()> {
                 }
 
                 // Get the diff from the last commit
-                let diff = git::get_last_commit_diff(&repo)?;
+                let diff = git::get_last_commit_diff(&repo, &diff_context)?;
                 if diff.is_empty() {
-                    println!(
+                    status!(
                         "{}",
                         "Error: Could not get changes from the last commit.".red()
                     );
-                    println!(
+                    status!(
                         "{}",
                         "Make sure you have at least one commit in your repository.".bright_black()
                     );
                     process::exit(1);
                 }
+                let diff_tokens = openai::count_token(&diff)?;
                 actor.add_message(Message::user(diff));
-                actor.used_tokens = system_len + extra_len;
+                actor.used_tokens = used_len + diff_tokens;
             } else {
                 // Normal commit mode - get diff from staged changes
                 let (diff, diff_tokens) = util::decide_diff(
                     &repo,
-                    system_len + extra_len,
+                    used_len,
                     options.model.context_size(),
                     options.always_select_files,
+                    &diff_context,
                 )?;
                 actor.add_message(Message::user(diff));
-                actor.used_tokens = system_len + extra_len + diff_tokens;
+                actor.used_tokens = used_len + diff_tokens;
             }
         }
         jj::VcsType::Jujutsu => {
@@ -163,8 +467,8 @@ async fn main() -> anyhow::Result<()> {
                 } else {
                     "No changes detected in Jujutsu working directory.".to_string()
                 };
-                println!("{}", revision_msg.red());
-                println!(
+                status!("{}", revision_msg.red());
+                status!(
                     "{}",
                     "Please make some changes before running turbocommit.".bright_black()
                 );
@@ -178,29 +482,73 @@ async fn main() -> anyhow::Result<()> {
 
             // Get the diff for the specified revision with file selection support
             let (diff, diff_tokens) = util::decide_diff_jj(
-                system_len + extra_len,
+                used_len,
                 options.model.context_size(),
                 options.always_select_files,
                 options.jj_revision.as_deref(),
+                &diff_context,
             )?;
 
             // If rewrite mode is enabled, include current description as hint
             if options.jj_rewrite {
                 if let Some(current_desc) = jj::get_jj_description(options.jj_revision.as_deref())?
                 {
-                    let hint_msg = format!("Current description: {}", current_desc);
+                    let mut variables = variables.clone();
+                    variables.insert("description", current_desc);
+                    let hint_msg = render(&options.prompts.current_description, &variables);
                     actor.add_message(Message::user(hint_msg));
                 }
             }
 
             actor.add_message(Message::user(diff));
-            actor.used_tokens = system_len + extra_len + diff_tokens;
+            actor.used_tokens = used_len + diff_tokens;
+        }
+        jj::VcsType::Patch => {
+            let diff_tokens = openai::count_token(&patch)?;
+            if used_len + diff_tokens > options.model.context_size() {
+                status!(
+                    "{} {}",
+                    "The request is too long!".red(),
+                    format!(
+                        "The request is ~{} tokens long, while the maximum is {}.",
+                        used_len + diff_tokens,
+                        options.model.context_size()
+                    )
+                    .bright_black()
+                );
+                process::exit(1);
+            }
+            actor.add_message(Message::user(patch));
+            actor.used_tokens = used_len + diff_tokens;
         }
     }
 
+    // Remember what the message is written for, so `--resume` can check it
+    if !options.dry_run
+        && options.format.is_none()
+        && options.message_file.is_none()
+        && !options.auto_commmit
+    {
+        actor.diff_hash =
+            session::diff_hash(&vcs_type, options.amend, options.jj_revision.as_deref())?;
+    }
+
     // Add any extra message from command line
-    if !options.msg.is_empty() {
-        actor.add_message(Message::user(options.msg));
+    if !extra_msg.is_empty() {
+        actor.add_message(Message::user(extra_msg));
+    }
+
+    if options.dry_run {
+        return actor.dry_run(options.format.as_deref());
+    }
+
+    if let Some(format) = &options.format {
+        actor.report(format).await?;
+        return Ok(());
+    }
+
+    if let Some(file) = &options.message_file {
+        return actor.write_message(file).await;
     }
 
     if options.auto_commmit {
@@ -214,15 +562,19 @@ async fn main() -> anyhow::Result<()> {
         util::check_version().await;
     }
 
-    if util::check_config_age(Duration::from_secs(60 * 60 * 24 * 30 * 6)) {
-        if !util::is_system_prompt_same_as_default(&config.system_msg) {
-            println!(
-                "\n{}\n{}\n{}",
-                "Your system prompt seems to be old.".yellow(),
-                "There is a new default recommended system prompt. To apply it, delete the `system_msg` field in your config file.".bright_black(),
-                "To get rid of this message, simply save your config file to change the last modified date.".bright_black()
-            );
-        }
+    if config.default_prompt_hash != migrate::default_prompt_hash()
+        && !util::is_system_prompt_same_as_default(&config.system_msg)
+    {
+        status!(
+            "\n{}\n{}\n{}",
+            "Your system prompt seems to be old.".yellow(),
+            "There is a new default recommended system prompt. To apply it, run `turbocommit config reset-prompt`.".bright_black(),
+            format!(
+                "To keep your own prompt and hide this message, run `turbocommit config set default_prompt_hash {}`.",
+                migrate::default_prompt_hash()
+            )
+            .bright_black()
+        );
     }
 
     Ok(())
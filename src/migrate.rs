@@ -0,0 +1,213 @@
+use serde_yaml::{Mapping, Value};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::util;
+
+/// Version written to new config files
+pub const CURRENT_VERSION: u64 = 2;
+
+/// Upgrades a config mapping from the previous version to `to`
+struct Migration {
+    to: u64,
+    description: &'static str,
+    apply: fn(&mut Mapping),
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        to: 1,
+        description: "remove sampling options and move to the gpt-5.4 model",
+        apply: to_v1,
+    },
+    Migration {
+        to: 2,
+        description: "track which default system prompt the config is based on",
+        apply: to_v2,
+    },
+];
+
+/// Short hash identifying a version of the default system prompt
+pub fn prompt_hash(prompt: &str) -> String {
+    let digest = Sha256::digest(prompt.as_bytes());
+    util::hex(&digest[..8])
+}
+
+pub fn default_prompt_hash() -> String {
+    Config::default().default_prompt_hash
+}
+
+/// Hashes of the default system prompts of earlier releases, oldest first
+const PAST_DEFAULT_PROMPT_HASHES: &[&str] = &["a5bef659ee1fb0a5"];
+
+/// Models of the earlier releases, which are no longer accepted
+const LEGACY_MODEL_PREFIXES: &[&str] = &["gpt-3.5", "gpt-4", "o1", "o3", "o4"];
+
+/// The single-model era configs carried sampling options and older model names
+fn to_v1(config: &mut Mapping) {
+    for key in [
+        "default_temperature",
+        "default_frequency_penalty",
+        "disable_print_as_stream",
+    ] {
+        config.remove(key);
+    }
+    let model = config.get("model").and_then(Value::as_str);
+    if model.is_some_and(|model| LEGACY_MODEL_PREFIXES.iter().any(|p| model.starts_with(p))) {
+        config.insert("model".into(), "gpt-5.4".into());
+    }
+}
+
+fn to_v2(config: &mut Mapping) {
+    let system_msg = config
+        .get("system_msg")
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    // An unmodified older default is upgraded, anything else is the user's own
+    let hash = if system_msg.trim().is_empty() || system_msg == Config::default().system_msg {
+        default_prompt_hash()
+    } else if PAST_DEFAULT_PROMPT_HASHES.contains(&prompt_hash(system_msg).as_str()) {
+        config.insert("system_msg".into(), Config::default().system_msg.into());
+        default_prompt_hash()
+    } else {
+        String::new()
+    };
+    config.insert("default_prompt_hash".into(), hash.into());
+}
+
+/// Upgrades a config to the current version and returns the version it had
+/// along with the applied migrations. Newer versions are left untouched.
+pub fn run(config: &mut Value) -> (u64, Vec<&'static str>) {
+    let Value::Mapping(map) = config else {
+        return (CURRENT_VERSION, Vec::new());
+    };
+    let from = map.get("version").and_then(Value::as_u64).unwrap_or(0);
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.to > from) {
+        (migration.apply)(map);
+        applied.push(migration.description);
+    }
+    if !applied.is_empty() {
+        map.insert("version".into(), CURRENT_VERSION.into());
+    }
+    (from, applied)
+}
+
+/// Replaces a system prompt that is an unmodified older default with the
+/// current default. Returns whether the prompt was replaced.
+pub fn refresh_default_prompt(config: &mut Value) -> bool {
+    let Value::Mapping(map) = config else {
+        return false;
+    };
+    let current = default_prompt_hash();
+    let stored = map.get("default_prompt_hash").and_then(Value::as_str);
+    let system_msg = map.get("system_msg").and_then(Value::as_str);
+    match (stored, system_msg) {
+        (Some(stored), Some(system_msg))
+            if !stored.is_empty() && stored != current && prompt_hash(system_msg) == stored =>
+        {
+            map.insert("system_msg".into(), Config::default().system_msg.into());
+            map.insert("default_prompt_hash".into(), current.into());
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_legacy_config() {
+        let mut config: Value = serde_yaml::from_str(
+            "model: gpt-4o\ndefault_temperature: 1.0\nsystem_msg: \"My own prompt\"\n",
+        )
+        .unwrap();
+        let (from, applied) = run(&mut config);
+        assert_eq!(from, 0);
+        assert_eq!(applied.len(), 2);
+        assert_eq!(config["version"].as_u64(), Some(CURRENT_VERSION));
+        assert_eq!(config["model"].as_str(), Some("gpt-5.4"));
+        assert!(config.get("default_temperature").is_none());
+        assert_eq!(config["default_prompt_hash"].as_str(), Some(""));
+
+        // Already current
+        let (from, applied) = run(&mut config);
+        assert_eq!(from, CURRENT_VERSION);
+        assert!(applied.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_past_default_prompt() {
+        // The default before convention presets, with hard-coded conventional rules
+        let past_default = Config::default()
+            .system_msg
+            .replace("high-quality commit", "high-quality conventional commit")
+            .replace(
+                "a commit header following the commit rules",
+                "a conventional commit header",
+            )
+            .replace(
+                "<body_rules>",
+                "<commit_rules>
+- Use this title shape: `<type>(optional-scope): description`
+- Allowed types: `feat`, `fix`, `docs`, `style`, `refactor`, `test`, `build`, `ci`, `chore`
+- Add `!` only for genuine breaking changes.
+- Keep the description imperative, specific, and without a trailing period.
+- Prefer lowercase at the start unless a proper noun, acronym, or identifier requires otherwise.
+- Use a scope only when it adds meaningful precision.
+- Avoid vague descriptions like `update files`, `improve code`, or `misc changes`.
+</commit_rules>
+
+<body_rules>",
+            );
+        let mut config = Value::Mapping(Mapping::new());
+        config["system_msg"] = past_default.into();
+        let (_, applied) = run(&mut config);
+        assert_eq!(applied.len(), 2);
+        assert_eq!(
+            config["system_msg"].as_str(),
+            Some(Config::default().system_msg.as_str())
+        );
+        assert_eq!(
+            config["default_prompt_hash"].as_str(),
+            Some(default_prompt_hash().as_str())
+        );
+    }
+
+    #[test]
+    fn test_newer_version_untouched() {
+        let mut config: Value = serde_yaml::from_str("version: 99\nmodel: gpt-6\n").unwrap();
+        let (from, applied) = run(&mut config);
+        assert_eq!(from, 99);
+        assert!(applied.is_empty());
+        assert_eq!(config["model"].as_str(), Some("gpt-6"));
+    }
+
+    #[test]
+    fn test_refresh_default_prompt() {
+        let old_default = "Old default prompt";
+        let mut config: Value = serde_yaml::from_str(&format!(
+            "system_msg: \"{}\"\ndefault_prompt_hash: \"{}\"\n",
+            old_default,
+            prompt_hash(old_default)
+        ))
+        .unwrap();
+        assert!(refresh_default_prompt(&mut config));
+        assert_eq!(
+            config["system_msg"].as_str(),
+            Some(Config::default().system_msg.as_str())
+        );
+        assert!(!refresh_default_prompt(&mut config));
+
+        // Customized prompts are kept
+        let mut config: Value = serde_yaml::from_str(&format!(
+            "system_msg: \"Edited prompt\"\ndefault_prompt_hash: \"{}\"\n",
+            prompt_hash(old_default)
+        ))
+        .unwrap();
+        assert!(!refresh_default_prompt(&mut config));
+    }
+}
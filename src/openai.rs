@@ -8,7 +8,7 @@ use std::{
     time::{Duration, Instant},
 };
 
-use crate::{debug_log::DebugLogger, spinner};
+use crate::{debug_log::DebugLogger, output::status, spinner};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(rename_all = "lowercase")]
@@ -63,7 +63,7 @@ impl Message {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct CommitSuggestion {
     pub title: String,
     #[serde(default)]
@@ -147,7 +147,7 @@ pub struct JsonSchemaFormat {
 }
 
 impl ResponseFormat {
-    fn commit_suggestions(suggestion_count: usize) -> Self {
+    fn commit_suggestions(suggestion_count: usize, title_description: &str) -> Self {
         let count = suggestion_count.max(1) as u64;
         Self {
             type_field: "json_schema".to_string(),
@@ -168,12 +168,12 @@ impl ResponseFormat {
                                 "properties": {
                                     "title": {
                                         "type": "string",
-                                        "description": "Conventional commit title (<type>(scope?): description)",
+                                        "description": title_description,
                                         "minLength": 1
                                     },
                                     "body": {
                                         "type": ["string", "null"],
-                                        "description": "Optional conventional commit body paragraph focusing on motivation (use null when not needed)"
+                                        "description": "Optional commit body paragraph focusing on motivation (use null when not needed)"
                                     }
                                 },
                                 "required": ["title", "body"]
@@ -196,10 +196,21 @@ impl Request {
             suggestion_count: normalized,
             reasoning_effort: None,
             verbosity: None,
-            response_format: Some(ResponseFormat::commit_suggestions(normalized)),
+            response_format: Some(ResponseFormat::commit_suggestions(
+                normalized,
+                "Conventional commit title (<type>(optional-scope): description)",
+            )),
         }
     }
 
+    pub fn with_title_description(mut self, description: &str) -> Self {
+        self.response_format = Some(ResponseFormat::commit_suggestions(
+            self.suggestion_count,
+            description,
+        ));
+        self
+    }
+
     pub fn with_reasoning_effort(mut self, effort: Option<String>) -> Self {
         self.reasoning_effort = effort;
         self
@@ -277,8 +288,8 @@ impl Request {
                 status, api_endpoint, error_details
             );
             debug_logger.log_error(&error_msg);
-            println!("{}", "API Error:".red().bold());
-            println!("{}", error_msg);
+            status!("{}", "API Error:".red().bold());
+            status!("{}", error_msg);
             process::exit(1);
         }
 
@@ -315,7 +326,7 @@ impl Request {
         }
 
         if envelope.suggestions.len() != self.suggestion_count {
-            println!(
+            status!(
                 "{} {} -> {}",
                 "Warning:".yellow(),
                 "Model returned a different number of suggestions than requested".bright_black(),
@@ -324,21 +335,21 @@ impl Request {
         }
 
         if debug {
-            println!("\n{}", "=== API Response ===".blue().bold());
-            println!("  Model: {}", self.model.purple());
-            println!("  Input tokens: {}", prompt_tokens.to_string().purple());
+            status!("\n{}", "=== API Response ===".blue().bold());
+            status!("  Model: {}", self.model.purple());
+            status!("  Input tokens: {}", prompt_tokens.to_string().purple());
             if let Some(usage) = &completion.usage {
-                println!(
+                status!(
                     "  Output tokens: {} (total: {})",
                     usage.completion_tokens.to_string().purple(),
                     usage.total_tokens.to_string().purple()
                 );
             }
-            println!(
+            status!(
                 "  Suggestions returned: {}",
                 envelope.suggestions.len().to_string().purple()
             );
-            println!(
+            status!(
                 "  Duration: {}",
                 format!("{:.1}s", duration.as_secs_f32()).purple()
             );
@@ -348,15 +359,19 @@ impl Request {
             suggestions: envelope.suggestions,
             usage: completion.usage,
             duration,
+            cached: false,
         })
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CompletionResult {
     pub suggestions: Vec<CommitSuggestion>,
     pub usage: Option<Usage>,
     pub duration: Duration,
+    /// Answered from the response cache, without a request
+    #[serde(skip)]
+    pub cached: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -418,16 +433,39 @@ struct ContentPart {
     text: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Usage {
     pub prompt_tokens: usize,
     pub completion_tokens: usize,
     pub total_tokens: usize,
     #[serde(default)]
+    pub prompt_tokens_details: PromptTokensDetails,
+    #[serde(default)]
     pub completion_tokens_details: CompletionTokensDetails,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+impl Usage {
+    /// Adds the usage of another request, e.g. a lint repair round
+    pub fn add(&mut self, other: &Usage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+        self.prompt_tokens_details.cached_tokens += other.prompt_tokens_details.cached_tokens;
+        let details = &mut self.completion_tokens_details;
+        let other = &other.completion_tokens_details;
+        details.reasoning_tokens += other.reasoning_tokens;
+        details.accepted_prediction_tokens += other.accepted_prediction_tokens;
+        details.rejected_prediction_tokens += other.rejected_prediction_tokens;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct PromptTokensDetails {
+    /// Prompt tokens served from the provider's prompt cache
+    pub cached_tokens: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct CompletionTokensDetails {
     pub reasoning_tokens: usize,
     pub accepted_prediction_tokens: usize,
@@ -503,6 +541,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_title_description_in_schema() {
+        let request = Request::new(
+            "gpt-5.4".to_string(),
+            vec![Message::user("test".to_string())],
+            2,
+        )
+        .with_title_description("Gitmoji commit title (<gitmoji> description)");
+
+        let json = serde_json::to_string(&request).expect("Failed to serialize");
+        assert!(json.contains("Gitmoji commit title (<gitmoji> description)"));
+        assert!(json.contains("\"minItems\":2"));
+    }
+
     #[test]
     fn commit_suggestion_to_message_body_optional() {
         let suggestion = CommitSuggestion {
@@ -520,4 +572,22 @@ mod tests {
         };
         assert_eq!(suggestion_no_body.as_commit_message(), "fix: bug");
     }
+
+    #[test]
+    fn test_usage_add() {
+        let mut usage: Usage = serde_json::from_str(
+            r#"{"prompt_tokens":100,"completion_tokens":20,"total_tokens":120,"prompt_tokens_details":{"cached_tokens":40}}"#,
+        )
+        .unwrap();
+        let round: Usage = serde_json::from_str(
+            r#"{"prompt_tokens":150,"completion_tokens":30,"total_tokens":180,"completion_tokens_details":{"reasoning_tokens":10,"accepted_prediction_tokens":0,"rejected_prediction_tokens":0}}"#,
+        )
+        .unwrap();
+        usage.add(&round);
+        assert_eq!(usage.prompt_tokens, 250);
+        assert_eq!(usage.completion_tokens, 50);
+        assert_eq!(usage.total_tokens, 300);
+        assert_eq!(usage.prompt_tokens_details.cached_tokens, 40);
+        assert_eq!(usage.completion_tokens_details.reasoning_tokens, 10);
+    }
 }
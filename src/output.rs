@@ -0,0 +1,27 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Output formats of `--format`, which print the suggestions for other programs
+pub const FORMATS: &[&str] = &["json", "plain"];
+
+/// Set while stdout only carries the machine-readable result
+static MACHINE_READABLE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_machine_readable(enabled: bool) {
+    MACHINE_READABLE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_machine_readable() -> bool {
+    MACHINE_READABLE.load(Ordering::Relaxed)
+}
+
+/// `println!` for status messages; they go to stderr in machine-readable mode
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::output::is_machine_readable() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+pub(crate) use status;
@@ -0,0 +1,242 @@
+use std::io::Read;
+
+use atty::Stream;
+use colored::Colorize;
+
+use crate::output::status;
+
+/// Reads a unified diff from a file, or from stdin for `-`
+pub fn read(source: &str) -> anyhow::Result<String> {
+    let diff = if source == "-" {
+        if atty::is(Stream::Stdin) {
+            status!(
+                "{}",
+                "Reading the diff from stdin, finish with Ctrl-D.".bright_black()
+            );
+        }
+        let mut diff = String::new();
+        std::io::stdin().read_to_string(&mut diff)?;
+        diff
+    } else {
+        std::fs::read_to_string(source)
+            .map_err(|err| anyhow::anyhow!("Could not read diff file {}: {}", source, err))?
+    };
+    if files(&diff).is_empty() {
+        let name = if source == "-" { "stdin" } else { source };
+        anyhow::bail!("{} doesn't contain a unified diff", name);
+    }
+    Ok(diff)
+}
+
+/// Remaining lines of the current hunk, from its `@@ -a,b +c,d @@` header
+#[derive(Default)]
+struct Hunk {
+    old: usize,
+    new: usize,
+}
+
+impl Hunk {
+    /// Line counts of a hunk header; a missing count means one line
+    fn lengths(header: &str) -> Option<(usize, usize)> {
+        let mut ranges = header.strip_prefix("@@ -")?.split_whitespace();
+        let count = |range: &str| match range.split_once(',') {
+            Some((_, count)) => count.parse().ok(),
+            None => Some(1),
+        };
+        let old = count(ranges.next()?)?;
+        let new = count(ranges.next()?.strip_prefix('+')?)?;
+        Some((old, new))
+    }
+
+    /// Whether `line` is content of the current hunk, so that content like
+    /// `--- x` (a removed `-- x`) isn't taken for a header. Starts a new hunk
+    /// at a hunk header.
+    fn consume(&mut self, line: &str) -> bool {
+        if self.old > 0 || self.new > 0 {
+            match line.chars().next() {
+                Some('-') => self.old = self.old.saturating_sub(1),
+                Some('+') => self.new = self.new.saturating_sub(1),
+                Some('\\') => {}
+                // Some tools strip the space of empty context lines
+                Some(' ' | '\r' | '\n') | None => {
+                    self.old = self.old.saturating_sub(1);
+                    self.new = self.new.saturating_sub(1);
+                }
+                // The counts were off, the hunk is over
+                Some(_) => {
+                    *self = Self::default();
+                    return false;
+                }
+            }
+            return true;
+        }
+        // `\ No newline at end of file` after the last line
+        if line.starts_with('\\') {
+            return true;
+        }
+        if let Some((old, new)) = Self::lengths(line) {
+            *self = Self { old, new };
+        }
+        false
+    }
+}
+
+/// Paths changed by a unified diff in order of appearance. The `a/` and `b/`
+/// prefixes of git and Mercurial diffs are removed.
+pub fn files(diff: &str) -> Vec<String> {
+    // SVN and Mercurial add a tab and a revision or date after the path
+    let path = |line: &str| line.split('\t').next().unwrap_or("").trim().to_string();
+
+    let mut files: Vec<String> = Vec::new();
+    let mut old = String::new();
+    let mut hunk = Hunk::default();
+    for line in diff.lines() {
+        if hunk.consume(line) {
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("--- ") {
+            old = path(rest);
+        } else if let Some(rest) = line.strip_prefix("+++ ") {
+            let new = path(rest);
+            let prefixed = (old.starts_with("a/") || old == "/dev/null")
+                && (new.starts_with("b/") || new == "/dev/null");
+            let file = if new == "/dev/null" { &old } else { &new };
+            if file == "/dev/null" || file.is_empty() {
+                continue;
+            }
+            let file = match prefixed {
+                true => file[2..].to_string(),
+                false => file.to_string(),
+            };
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+    files
+}
+
+/// Splits a unified diff into one section per file, each with its path
+pub fn split(diff: &str) -> Vec<(String, String)> {
+    let mut sections: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut hunk = Hunk::default();
+    let mut lines = diff.split_inclusive('\n').peekable();
+    while let Some(line) = lines.next() {
+        // A new file starts at its header, or at a `---` line directly followed
+        // by `+++` once the current section is past its own header
+        let starts_file = !hunk.consume(line)
+            && (line.starts_with("diff ")
+                || line.starts_with("Index: ")
+                || (line.starts_with("--- ")
+                    && lines.peek().is_some_and(|next| next.starts_with("+++ "))
+                    && current.lines().any(|l| {
+                        l.starts_with("+++ ")
+                            || l.starts_with("@@")
+                            || l.starts_with("Binary files")
+                    })));
+        if starts_file && !current.trim().is_empty() {
+            sections.push(std::mem::take(&mut current));
+        }
+        current.push_str(line);
+    }
+    if !current.trim().is_empty() {
+        sections.push(current);
+    }
+
+    sections
+        .into_iter()
+        .map(|section| {
+            let file = files(&section).into_iter().next().unwrap_or_else(|| {
+                // Binary files only have the `diff --git a/x b/x` header
+                section
+                    .lines()
+                    .next()
+                    .and_then(|header| header.rsplit(" b/").next())
+                    .unwrap_or_default()
+                    .to_string()
+            });
+            (file, section)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files() {
+        let git = "diff --git a/src/a.rs b/src/a.rs
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1 +1 @@
+-old
++new
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1 @@
++hi
+";
+        assert_eq!(files(git), vec!["src/a.rs", "gone.txt", "new.txt"]);
+
+        let svn = "Index: lib/util.c
+===================================================================
+--- lib/util.c\t(revision 41)
++++ lib/util.c\t(working copy)
+@@ -1 +1 @@
+-a
++b
+";
+        assert_eq!(files(svn), vec!["lib/util.c"]);
+        assert!(files("just some text").is_empty());
+
+        // Removed `-- ` and added `++ ` lines look like file headers
+        let sql = "--- a/schema.sql
++++ b/schema.sql
+@@ -1,3 +1,3 @@
+ create table t;
+--- old comment
++++ new comment
+ drop table u;
+\\ No newline at end of file
+--- a/b.sql
++++ b/b.sql
+@@ -1 +1 @@
+-x
++y
+";
+        assert_eq!(files(sql), vec!["schema.sql", "b.sql"]);
+        assert_eq!(split(sql).len(), 2);
+    }
+
+    #[test]
+    fn test_split() {
+        let diff = "diff --git a/a.rs b/a.rs
+--- a/a.rs
++++ b/a.rs
+@@ -1 +1 @@
+-old
++new
+diff --git a/logo.png b/logo.png
+Binary files a/logo.png and b/logo.png differ
+--- b.txt
++++ b.txt
+@@ -1 +1 @@
+-x
++y
+";
+        let sections = split(diff);
+        let names: Vec<&str> = sections.iter().map(|(file, _)| file.as_str()).collect();
+        assert_eq!(names, vec!["a.rs", "logo.png", "b.txt"]);
+        assert!(sections[0].1.ends_with("+new\n"));
+        assert_eq!(
+            sections.iter().map(|(_, s)| s.as_str()).collect::<String>(),
+            diff
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::process;
+
+use colored::Colorize;
+
+use crate::actor::Actor;
+use crate::cli::Options;
+use crate::openai::{self, Message};
+use crate::output::status;
+use crate::{git, jj, language};
+
+/// Instructions for drafting a pull request instead of a commit message
+const SYSTEM_MSG: &str = "<role>
+You write pull request descriptions from the changes of a branch.
+Your job is to tell a reviewer what the branch changes and why, so they know what to look at.
+</role>
+
+<inputs>
+- You will receive the messages of the branch's commits, oldest first.
+- You will receive the diff of the branch against the branch it will be merged into.
+- You may receive additional instructions from the user.
+</inputs>
+
+<output_contract>
+- Respond with JSON only.
+- The JSON must satisfy the provided structured-output schema exactly.
+- Return exactly the requested number of suggestions.
+- Each suggestion must contain:
+  - `title`: the pull request title, a short imperative summary under 72 characters
+  - `body`: the pull request description in Markdown
+- Do not include markdown fences around the JSON, explanations, or extra keys.
+</output_contract>
+
+<body_rules>
+- Open with one or two plain sentences saying what the branch does and why.
+- Follow with a short bullet list of the notable changes, grouped by intent rather than by file.
+- Mention anything a reviewer should check carefully, such as migrations, breaking changes, or new configuration.
+- Do not restate the diff line by line or list every touched file.
+- Do not invent testing, issues, or links that the inputs don't show.
+</body_rules>";
+
+/// Drafts a pull request title and description for the checked-out branch
+pub async fn run(base: Option<&str>, mut options: Options, api_key: String) -> anyhow::Result<()> {
+    let repo = git::get_repo()?;
+    let Some(base) = base.map(String::from).or_else(|| git::default_base(&repo)) else {
+        status!(
+            "{} {}",
+            "No base branch found.".red(),
+            "Name the branch the pull request targets with --base.".bright_black()
+        );
+        process::exit(1);
+    };
+    let (diff, commits) = git::branch_changes(&repo, &base, &options.diff_context())?;
+    if diff.is_empty() {
+        status!(
+            "{} {}",
+            format!("No changes since {}.", base).red(),
+            "Commit the changes of the branch first.".bright_black()
+        );
+        process::exit(1);
+    }
+    status!(
+        "{}",
+        format!("Describing the changes since {}", base).bright_black()
+    );
+
+    // The commit message rules don't apply to a pull request
+    options.lint.enabled = false;
+    options.tickets.mode = "off".to_string();
+    let mut actor = Actor::new(
+        options.clone(),
+        api_key,
+        options.api_endpoint.clone(),
+        jj::VcsType::Git,
+    );
+    actor.title_description = "Pull request title".to_string();
+
+    let mut system_msg = SYSTEM_MSG.to_string();
+    if let Some(language) = language::prompt(&options.language) {
+        system_msg = format!("{}\n\n{}", system_msg, language);
+    }
+    let commits = format!("Commits on the branch:\n\n{}", commits.join("\n\n---\n\n"));
+    let used_len = openai::count_token(&system_msg)?
+        + openai::count_token(&commits)?
+        + openai::count_token(&options.msg)?;
+    let diff_len = openai::count_token(&diff)?;
+    if used_len + diff_len > options.model.context_size() {
+        status!(
+            "{} {}",
+            "The request is too long!".red(),
+            format!(
+                "The request is ~{} tokens long, while the maximum is {}.",
+                used_len + diff_len,
+                options.model.context_size()
+            )
+            .bright_black()
+        );
+        process::exit(1);
+    }
+    actor.add_message(Message::system(system_msg));
+    actor.add_message(Message::user(commits));
+    actor.add_message(Message::user(diff));
+    if !options.msg.is_empty() {
+        actor.add_message(Message::user(options.msg.clone()));
+    }
+    actor.used_tokens = used_len + diff_len;
+
+    if options.dry_run {
+        return actor.dry_run(options.format.as_deref());
+    }
+    actor
+        .report(options.format.as_deref().unwrap_or("plain"))
+        .await
+}
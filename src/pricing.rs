@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Output tokens assumed per suggestion when estimating a request before it is sent
+pub const ESTIMATED_OUTPUT_TOKENS_PER_SUGGESTION: usize = 80;
+
+/// Key of the prices used for models without their own entry
+pub const DEFAULT_MODEL: &str = "default";
+
+/// Prices of one model in USD per million tokens
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ModelPricing {
+    pub input: f64,
+    /// Input tokens served from the provider's prompt cache
+    pub cached_input: f64,
+    /// Completion tokens, including reasoning tokens
+    pub output: f64,
+}
+
+impl Default for ModelPricing {
+    fn default() -> Self {
+        // gpt-5.4 list prices
+        Self {
+            input: 2.5,
+            cached_input: 0.25,
+            output: 15.0,
+        }
+    }
+}
+
+impl ModelPricing {
+    /// Whether all prices are finite and not negative
+    pub fn is_valid(&self) -> bool {
+        [self.input, self.cached_input, self.output]
+            .iter()
+            .all(|price| price.is_finite() && *price >= 0.0)
+    }
+
+    /// Cost in USD of a request with the given token counts
+    pub fn cost(&self, input: usize, cached_input: usize, output: usize) -> f64 {
+        let uncached = input.saturating_sub(cached_input);
+        (uncached as f64 * self.input
+            + cached_input as f64 * self.cached_input
+            + output as f64 * self.output)
+            / 1_000_000.0
+    }
+}
+
+/// Model prices by model name, for cost estimates and reports. The `default`
+/// entry applies to models without their own.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct Pricing(pub BTreeMap<String, ModelPricing>);
+
+impl Default for Pricing {
+    fn default() -> Self {
+        Self(BTreeMap::from([(
+            DEFAULT_MODEL.to_string(),
+            ModelPricing::default(),
+        )]))
+    }
+}
+
+impl Pricing {
+    /// Whether all prices are valid
+    pub fn is_valid(&self) -> bool {
+        self.0.values().all(ModelPricing::is_valid)
+    }
+
+    /// The prices of `model`, or the default prices
+    pub fn for_model(&self, model: &str) -> ModelPricing {
+        self.0
+            .get(model)
+            .or_else(|| self.0.get(DEFAULT_MODEL))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Output tokens expected for a request asking for `suggestions` suggestions
+    pub fn estimated_output(suggestions: usize) -> usize {
+        suggestions * ESTIMATED_OUTPUT_TOKENS_PER_SUGGESTION
+    }
+}
+
+/// Formats a USD amount with enough precision for single requests
+pub fn format_cost(cost: f64) -> String {
+    if cost < 0.01 {
+        format!("${:.4}", cost)
+    } else {
+        format!("${:.2}", cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost() {
+        let pricing = ModelPricing::default();
+        assert_eq!(pricing.cost(1_000_000, 0, 0), 2.5);
+        assert_eq!(pricing.cost(1_000_000, 1_000_000, 0), 0.25);
+        assert_eq!(pricing.cost(0, 0, 100_000), 1.5);
+        assert_eq!(format_cost(0.00123), "$0.0012");
+        assert_eq!(format_cost(1.5), "$1.50");
+
+        assert!(pricing.is_valid());
+        let invalid = ModelPricing {
+            output: -1.0,
+            ..ModelPricing::default()
+        };
+        assert!(!invalid.is_valid());
+    }
+
+    #[test]
+    fn test_for_model() {
+        let mini = ModelPricing {
+            input: 0.25,
+            cached_input: 0.025,
+            output: 2.0,
+        };
+        let mut pricing = Pricing::default();
+        pricing.0.insert("gpt-5.4-mini".to_string(), mini.clone());
+        assert_eq!(pricing.for_model("gpt-5.4-mini"), mini);
+        assert_eq!(pricing.for_model("gpt-5.4"), ModelPricing::default());
+        assert!(pricing.is_valid());
+
+        // Without a default entry the built-in prices apply
+        let pricing: Pricing = serde_yaml::from_str("gpt-5.4-mini:\n  input: 0.25\n").unwrap();
+        assert_eq!(pricing.for_model("gpt-5.4-mini").input, 0.25);
+        assert_eq!(pricing.for_model("gpt-5.4-mini").output, 15.0);
+        assert_eq!(pricing.for_model("other"), ModelPricing::default());
+    }
+}
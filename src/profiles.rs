@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::model;
+
+/// Settings that override the top-level config when the profile is active
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_env_var: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<model::Model>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verbosity: Option<String>,
+    /// Selects the profile automatically when no `--profile` is given
+    #[serde(rename = "match")]
+    pub matches: ProfileMatch,
+}
+
+/// Wildcard patterns (`*` matches anything) for automatic profile selection
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ProfileMatch {
+    /// Patterns for the repository root; a leading `~` is the home directory
+    pub paths: Vec<String>,
+    /// Patterns for any remote URL of the repository
+    pub remotes: Vec<String>,
+}
+
+/// Matches `text` against a pattern where `*` stands for any run of characters
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern
+        .split('*')
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join(".*");
+    Regex::new(&format!("^{}$", pattern)).is_ok_and(|re| re.is_match(text))
+}
+
+fn expand_home(pattern: &str) -> String {
+    match (pattern.strip_prefix('~'), home::home_dir()) {
+        (Some(rest), Some(home)) => format!("{}{}", home.display(), rest),
+        _ => pattern.to_string(),
+    }
+}
+
+impl ProfileMatch {
+    fn matches(&self, root: Option<&Path>, remotes: &[String]) -> bool {
+        let path_match = root.is_some_and(|root| {
+            let root = root.to_string_lossy();
+            self.paths
+                .iter()
+                .any(|pattern| wildcard_match(expand_home(pattern).trim_end_matches('/'), &root))
+        });
+        path_match
+            || self
+                .remotes
+                .iter()
+                .any(|pattern| remotes.iter().any(|url| wildcard_match(pattern, url)))
+    }
+}
+
+/// Returns the first profile (in name order) matching the repository root or
+/// one of its remote URLs
+pub fn detect<'a>(
+    profiles: &'a BTreeMap<String, Profile>,
+    root: Option<&Path>,
+    remotes: &[String],
+) -> Option<&'a str> {
+    profiles
+        .iter()
+        .find(|(_, profile)| profile.matches.matches(root, remotes))
+        .map(|(name, _)| name.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(paths: &[&str], remotes: &[&str]) -> Profile {
+        Profile {
+            matches: ProfileMatch {
+                paths: paths.iter().map(|p| p.to_string()).collect(),
+                remotes: remotes.iter().map(|r| r.to_string()).collect(),
+            },
+            ..Profile::default()
+        }
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match(
+            "*github.com:acme/*",
+            "git@github.com:acme/api.git"
+        ));
+        assert!(!wildcard_match(
+            "*github.com/acme/*",
+            "git@github.com:acme/api.git"
+        ));
+        assert!(wildcard_match("/work/*", "/work/api"));
+        assert!(!wildcard_match("/work/*", "/home/work/api"));
+    }
+
+    #[test]
+    fn test_detect() {
+        let mut profiles = BTreeMap::new();
+        profiles.insert(
+            "work-azure".to_string(),
+            profile(&["/srv/work/*"], &["*github.com:acme/*"]),
+        );
+        profiles.insert("local-ollama".to_string(), profile(&[], &[]));
+
+        let remotes = vec!["git@github.com:acme/api.git".to_string()];
+        assert_eq!(
+            detect(&profiles, Some(Path::new("/tmp/api")), &remotes),
+            Some("work-azure")
+        );
+        assert_eq!(
+            detect(&profiles, Some(Path::new("/srv/work/api")), &[]),
+            Some("work-azure")
+        );
+        assert_eq!(detect(&profiles, Some(Path::new("/tmp/api")), &[]), None);
+        assert_eq!(detect(&profiles, None, &[]), None);
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Scope inference modes accepted in the config and on the command line
+pub const MODES: &[&str] = &["off", "hint", "strict"];
+
+/// A directory of the repository that maps to a commit scope
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopeRoot {
+    pub path: String,
+    pub scope: String,
+}
+
+impl ScopeRoot {
+    fn from_path(path: &str) -> Option<Self> {
+        let path = path.trim().trim_start_matches("./").trim_end_matches('/');
+        if path.is_empty() || path == "." {
+            return None;
+        }
+        let scope = path.rsplit('/').next()?.to_string();
+        Some(Self {
+            path: path.to_string(),
+            scope,
+        })
+    }
+}
+
+/// Expands workspace member patterns; supports a trailing `*` or `**` component
+fn expand(root: &Path, patterns: &[String]) -> Vec<String> {
+    let mut paths = Vec::new();
+    for pattern in patterns {
+        if pattern.starts_with('!') {
+            continue;
+        }
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        match pattern
+            .strip_suffix("/**")
+            .or_else(|| pattern.strip_suffix("/*"))
+        {
+            Some(parent) => {
+                let Ok(entries) = std::fs::read_dir(root.join(parent)) else {
+                    continue;
+                };
+                let mut dirs: Vec<String> = entries
+                    .filter_map(Result::ok)
+                    .filter(|e| e.path().is_dir())
+                    .filter_map(|e| e.file_name().to_str().map(|n| format!("{parent}/{n}")))
+                    .filter(|p| !p.rsplit('/').next().unwrap_or("").starts_with('.'))
+                    .collect();
+                dirs.sort();
+                paths.extend(dirs);
+            }
+            None if !pattern.contains('*') => paths.push(pattern.to_string()),
+            None => {}
+        }
+    }
+    paths
+}
+
+fn cargo_members(root: &Path) -> Vec<String> {
+    let Ok(manifest) = std::fs::read_to_string(root.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+    let Ok(doc) = manifest.parse::<toml_edit::DocumentMut>() else {
+        return Vec::new();
+    };
+    let members = doc
+        .get("workspace")
+        .and_then(|w| w.get("members"))
+        .and_then(|m| m.as_array())
+        .map(|array| {
+            array
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    expand(root, &members)
+}
+
+fn package_json_members(root: &Path) -> Vec<String> {
+    let Ok(content) = std::fs::read_to_string(root.join("package.json")) else {
+        return Vec::new();
+    };
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) else {
+        return Vec::new();
+    };
+    // Either `"workspaces": [...]` or `"workspaces": { "packages": [...] }`
+    let workspaces = json.get("workspaces");
+    let list = workspaces
+        .and_then(|w| w.as_array())
+        .or_else(|| workspaces.and_then(|w| w.get("packages")?.as_array()));
+    let members: Vec<String> = list
+        .map(|array| {
+            array
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    expand(root, &members)
+}
+
+fn pnpm_members(root: &Path) -> Vec<String> {
+    let Ok(content) = std::fs::read_to_string(root.join("pnpm-workspace.yaml")) else {
+        return Vec::new();
+    };
+    let Ok(yaml) = serde_yaml::from_str::<serde_yaml::Value>(&content) else {
+        return Vec::new();
+    };
+    let members: Vec<String> = yaml
+        .get("packages")
+        .and_then(|p| p.as_sequence())
+        .map(|seq| {
+            seq.iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    expand(root, &members)
+}
+
+fn go_work_members(root: &Path) -> Vec<String> {
+    let Ok(content) = std::fs::read_to_string(root.join("go.work")) else {
+        return Vec::new();
+    };
+    let mut members = Vec::new();
+    let mut in_block = false;
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if in_block {
+            if line == ")" {
+                in_block = false;
+            } else if !line.is_empty() {
+                members.push(line.to_string());
+            }
+        } else if let Some(rest) = line.strip_prefix("use") {
+            let rest = rest.trim();
+            if rest == "(" {
+                in_block = true;
+            } else if !rest.is_empty() {
+                members.push(rest.to_string());
+            }
+        }
+    }
+    expand(root, &members)
+}
+
+/// Detects scope roots from the configured path map and from Cargo, npm/yarn,
+/// pnpm and Go workspaces. Configured entries take precedence.
+pub fn detect(root: &Path, map: &BTreeMap<String, String>) -> Vec<ScopeRoot> {
+    let mut roots: Vec<ScopeRoot> = map
+        .iter()
+        .filter_map(|(path, scope)| {
+            ScopeRoot::from_path(path).map(|r| ScopeRoot {
+                scope: scope.clone(),
+                ..r
+            })
+        })
+        .collect();
+
+    let detected = cargo_members(root)
+        .into_iter()
+        .chain(package_json_members(root))
+        .chain(pnpm_members(root))
+        .chain(go_work_members(root));
+    for path in detected {
+        if let Some(scope_root) = ScopeRoot::from_path(&path) {
+            if !roots.iter().any(|r| r.path == scope_root.path) {
+                roots.push(scope_root);
+            }
+        }
+    }
+    roots
+}
+
+/// Returns the scopes whose roots contain any of the files, using the most
+/// specific root for each file
+pub fn touched_scopes(roots: &[ScopeRoot], files: &[String]) -> Vec<String> {
+    let mut touched = Vec::new();
+    for file in files {
+        let best = roots
+            .iter()
+            .filter(|r| file == &r.path || file.starts_with(&format!("{}/", r.path)))
+            .max_by_key(|r| r.path.len());
+        if let Some(root) = best {
+            if !touched.contains(&root.scope) {
+                touched.push(root.scope.clone());
+            }
+        }
+    }
+    touched.sort();
+    touched
+}
+
+/// Builds a user message describing the known scopes and the ones this change touches
+pub fn scope_hint(roots: &[ScopeRoot], touched: &[String], strict: bool) -> Option<String> {
+    if roots.is_empty() {
+        return None;
+    }
+    let mut available: Vec<&str> = roots.iter().map(|r| r.scope.as_str()).collect();
+    available.sort();
+    available.dedup();
+
+    let mut hint = format!(
+        "Scopes derived from the repository layout: {}.",
+        available.join(", ")
+    );
+    if !touched.is_empty() {
+        hint.push_str(&format!(" This change touches: {}.", touched.join(", ")));
+    }
+    if strict {
+        hint.push_str(" The scope MUST be one of the scopes above, or omitted entirely. Never invent other scope names.");
+    } else {
+        hint.push_str(" Prefer these scope names over invented ones.");
+    }
+    Some(hint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_detect_cargo_and_map() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\", \"tools/xtask\"]\n",
+        )
+        .unwrap();
+        fs::create_dir_all(dir.path().join("crates/api")).unwrap();
+        fs::create_dir_all(dir.path().join("crates/cli")).unwrap();
+
+        let mut map = BTreeMap::new();
+        map.insert("docs".to_string(), "documentation".to_string());
+
+        let roots = detect(dir.path(), &map);
+        let scopes: Vec<&str> = roots.iter().map(|r| r.scope.as_str()).collect();
+        assert_eq!(scopes, vec!["documentation", "api", "cli", "xtask"]);
+    }
+
+    #[test]
+    fn test_detect_package_json_and_go_work() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"workspaces": {"packages": ["packages/web"]}}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("go.work"),
+            "go 1.22\n\nuse (\n\t./services/auth // auth service\n)\nuse ./tools\n",
+        )
+        .unwrap();
+
+        let roots = detect(dir.path(), &BTreeMap::new());
+        let paths: Vec<&str> = roots.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["packages/web", "services/auth", "tools"]);
+    }
+
+    #[test]
+    fn test_touched_scopes_prefers_most_specific_root() {
+        let roots = vec![
+            ScopeRoot::from_path("crates").unwrap(),
+            ScopeRoot::from_path("crates/api").unwrap(),
+        ];
+        let files = vec![
+            "crates/api/src/lib.rs".to_string(),
+            "crates/other.rs".to_string(),
+            "crates/apix/lib.rs".to_string(),
+            "README.md".to_string(),
+        ];
+        assert_eq!(touched_scopes(&roots, &files), vec!["api", "crates"]);
+    }
+
+    #[test]
+    fn test_scope_hint() {
+        assert_eq!(scope_hint(&[], &[], false), None);
+
+        let roots = vec![ScopeRoot::from_path("crates/api").unwrap()];
+        let hint = scope_hint(&roots, &["api".to_string()], true).unwrap();
+        assert!(hint.contains("This change touches: api."));
+        assert!(hint.contains("MUST"));
+    }
+}
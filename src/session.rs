@@ -0,0 +1,118 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::diff_context::DiffContext;
+use crate::{git, jj, openai, util};
+
+/// File name of the session inside `.git` or `.jj`
+const FILE_NAME: &str = "turbocommit-session.json";
+
+/// The conversation and candidate message of an unfinished Commit/Edit/Revise
+/// loop, saved so `--resume` can continue it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Session {
+    pub messages: Vec<openai::Message>,
+    /// The message that would be committed, with trailers
+    pub message: String,
+    pub trailers: Vec<String>,
+    pub tickets: Vec<String>,
+    pub amend: bool,
+    pub jj_revision: Option<String>,
+    /// Hash of the changes the message was written for, see [`diff_hash`]
+    #[serde(default)]
+    pub diff_hash: String,
+    /// Tokens of the conversation, for the token count shown when revising
+    #[serde(default)]
+    pub used_tokens: usize,
+}
+
+/// Hash of the changes a message describes: the staged diff, the last commit's
+/// diff when amending, or the diff of the jj revision. Empty for patches.
+pub fn diff_hash(
+    vcs_type: &jj::VcsType,
+    amend: bool,
+    jj_revision: Option<&str>,
+) -> anyhow::Result<String> {
+    let context = DiffContext {
+        symbols: false,
+        ..DiffContext::default()
+    };
+    let diff = match vcs_type {
+        jj::VcsType::Git => {
+            let repo = git::get_repo()?;
+            match amend {
+                true => git::get_last_commit_diff(&repo, &context)?,
+                false => git::diff(&repo, &git::staged_files(&repo)?, &context)?,
+            }
+        }
+        jj::VcsType::Jujutsu => jj::get_jj_diff(jj_revision, &context)?,
+        jj::VcsType::Patch => return Ok(String::new()),
+    };
+    Ok(util::hex(&Sha256::digest(diff.as_bytes())))
+}
+
+impl Session {
+    /// Where the session of the current repository is kept, if there is one
+    pub fn path(vcs_type: &jj::VcsType) -> Option<PathBuf> {
+        match vcs_type {
+            // Inside the git dir, so linked worktrees get their own session
+            jj::VcsType::Git => git::get_repo().ok().map(|repo| repo.path().join(FILE_NAME)),
+            jj::VcsType::Jujutsu => jj::repo_root().map(|root| root.join(".jj").join(FILE_NAME)),
+            jj::VcsType::Patch => None,
+        }
+    }
+
+    pub fn load_from(path: &Path) -> anyhow::Result<Option<Self>> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save_to(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Removes the saved session once the loop has finished
+    pub fn clear(path: &Path) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_save_load_clear() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(FILE_NAME);
+        assert_eq!(Session::load_from(&path).unwrap(), None);
+
+        let session = Session {
+            messages: vec![
+                openai::Message::system("rules".to_string()),
+                openai::Message::user("diff".to_string()),
+            ],
+            message: "feat: add x\n\nRefs: PROJ-1".to_string(),
+            trailers: vec!["Refs: PROJ-1".to_string()],
+            tickets: vec!["PROJ-1".to_string()],
+            amend: false,
+            jj_revision: None,
+            diff_hash: "abc".to_string(),
+            used_tokens: 1200,
+        };
+        session.save_to(&path).unwrap();
+        assert_eq!(Session::load_from(&path).unwrap(), Some(session));
+
+        Session::clear(&path);
+        assert!(!path.exists());
+        std::fs::write(&path, "not json").unwrap();
+        assert!(Session::load_from(&path).is_err());
+    }
+}
@@ -5,6 +5,8 @@ use std::{
 
 use atty::Stream;
 use colored::Colorize;
+
+use crate::output;
 use tokio::{
     sync::oneshot,
     task::JoinHandle,
@@ -19,7 +21,7 @@ pub struct Spinner {
 
 impl Spinner {
     pub fn start(label: String) -> Option<Self> {
-        if !atty::is(Stream::Stdout) {
+        if output::is_machine_readable() || !atty::is(Stream::Stdout) {
             return None;
         }
         if !terminal_supports_emoji::supports_emoji(terminal_supports_emoji::Stream::Stdout) {
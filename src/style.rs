@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+/// Extracts the scope from a conventional commit title like `feat(api): ...`
+fn scope_of(title: &str) -> Option<&str> {
+    let open = title.find('(')?;
+    let colon = title.find(':')?;
+    if open > colon {
+        return None;
+    }
+    let close = title[open..].find(')')? + open;
+    let kind = &title[..open];
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    let scope = title[open + 1..close].trim();
+    (!scope.is_empty()).then_some(scope)
+}
+
+/// Counts scopes used in commit titles, most frequent first
+pub fn frequent_scopes(messages: &[String]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for message in messages {
+        let title = message.lines().next().unwrap_or("");
+        if let Some(scope) = scope_of(title) {
+            *counts.entry(scope).or_default() += 1;
+        }
+    }
+
+    let mut scopes: Vec<(String, usize)> = counts
+        .into_iter()
+        .map(|(scope, count)| (scope.to_string(), count))
+        .collect();
+    scopes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    scopes
+}
+
+/// Builds a user message presenting recent commit messages as style examples
+pub fn style_hint(messages: &[String]) -> Option<String> {
+    if messages.is_empty() {
+        return None;
+    }
+
+    let mut hint = String::from(
+        "Recent commit messages from this repository. Match their conventions \
+         (scope names, capitalization, body style) unless they conflict with the commit rules:\n",
+    );
+    for message in messages {
+        hint.push_str("---\n");
+        hint.push_str(message.trim());
+        hint.push('\n');
+    }
+    hint.push_str("---");
+
+    let scopes = frequent_scopes(messages);
+    if !scopes.is_empty() {
+        let list: Vec<String> = scopes
+            .iter()
+            .take(10)
+            .map(|(scope, count)| format!("{} ({})", scope, count))
+            .collect();
+        hint.push_str(&format!("\nFrequently used scopes: {}", list.join(", ")));
+    }
+
+    Some(hint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_of() {
+        assert_eq!(scope_of("feat(api): add endpoint"), Some("api"));
+        assert_eq!(scope_of("fix(cli)!: drop flag"), Some("cli"));
+        assert_eq!(scope_of("fix: no scope (really)"), None);
+        assert_eq!(scope_of("Merge branch 'main' (old)"), None);
+    }
+
+    #[test]
+    fn test_frequent_scopes() {
+        let messages = vec![
+            "feat(api): one".to_string(),
+            "fix(cli): two".to_string(),
+            "fix(api): three\n\nbody".to_string(),
+            "chore: four".to_string(),
+        ];
+        assert_eq!(
+            frequent_scopes(&messages),
+            vec![("api".to_string(), 2), ("cli".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_style_hint() {
+        assert_eq!(style_hint(&[]), None);
+
+        let hint = style_hint(&["feat(api): one".to_string()]).unwrap();
+        assert!(hint.contains("---\nfeat(api): one\n---"));
+        assert!(hint.ends_with("Frequently used scopes: api (1)"));
+    }
+}
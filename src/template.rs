@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{jj, util};
+
+/// Variables available in the system message and the message templates
+pub const VARIABLES: &[&str] = &[
+    "branch",
+    "repo_name",
+    "staged_files",
+    "recent_commits",
+    "language",
+    "ticket",
+    "message",
+    "description",
+];
+
+/// Nested includes deeper than this are assumed to be a cycle
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Values of the template variables; unset variables render empty
+pub type Variables = BTreeMap<&'static str, String>;
+
+/// Templates of the user messages and where partials are included from
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PromptConfig {
+    /// Directory of `{{> name}}` partials; empty means `~/.turbocommit/prompts`,
+    /// relative paths start at the repository root
+    pub dir: String,
+    /// Wraps the explanation given on the command line, `{{message}}`
+    pub instruction: String,
+    /// Wraps the current description in jj rewrite mode, `{{description}}`
+    pub current_description: String,
+}
+
+impl Default for PromptConfig {
+    fn default() -> Self {
+        Self {
+            dir: String::new(),
+            instruction: String::from("User Explanation/Instruction: '{{message}}'"),
+            current_description: String::from("Current description: {{description}}"),
+        }
+    }
+}
+
+impl PromptConfig {
+    /// The resolved partials directory
+    pub fn dir(&self) -> PathBuf {
+        let dir = util::config_path(&self.dir, "prompts");
+        if dir.is_relative() {
+            jj::repo_root().unwrap_or_default().join(dir)
+        } else {
+            dir
+        }
+    }
+}
+
+/// Whether the text uses template syntax at all
+pub fn is_template(text: &str) -> bool {
+    text.contains("{{")
+}
+
+enum Tag<'a> {
+    Variable(&'a str),
+    Include(&'a str),
+}
+
+/// Splits a template into literal text and tags
+fn parse(template: &str) -> anyhow::Result<Vec<(&str, Option<Tag<'_>>)>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            anyhow::bail!("Unclosed `{{{{` in prompt template");
+        };
+        let inner = rest[start + 2..start + len].trim();
+        let tag = match inner.strip_prefix('>') {
+            Some(name) if !name.trim().is_empty() => Tag::Include(name.trim()),
+            Some(_) => anyhow::bail!("Missing partial name in `{{{{>}}}}`"),
+            None if VARIABLES.contains(&inner) => Tag::Variable(inner),
+            None => anyhow::bail!(
+                "Unknown prompt template variable `{{{{{}}}}}`. Available: {}",
+                inner,
+                VARIABLES.join(", ")
+            ),
+        };
+        parts.push((&rest[..start], Some(tag)));
+        rest = &rest[start + len + 2..];
+    }
+    parts.push((rest, None));
+    Ok(parts)
+}
+
+/// Returns why the template can't be rendered, without resolving includes
+pub fn check(template: &str) -> Option<String> {
+    parse(template).err().map(|err| err.to_string())
+}
+
+/// Finds a partial by its name, with or without a `.md` or `.txt` extension.
+/// Partials are files directly in `dir`, names can't point anywhere else.
+fn read_partial(dir: &Path, name: &str) -> anyhow::Result<String> {
+    if name.contains(['/', '\\']) || name.contains("..") || Path::new(name).is_absolute() {
+        anyhow::bail!(
+            "Invalid prompt partial name '{}', partials are files in {}",
+            name,
+            dir.display()
+        );
+    }
+    let Ok(root) = dir.canonicalize() else {
+        anyhow::bail!("Prompt partial '{}' not found in {}", name, dir.display());
+    };
+    for candidate in [
+        name.to_string(),
+        format!("{}.md", name),
+        format!("{}.txt", name),
+    ] {
+        // Symlinks may not lead out of the prompts directory either
+        let Ok(path) = dir.join(candidate).canonicalize() else {
+            continue;
+        };
+        if !path.starts_with(&root) {
+            anyhow::bail!(
+                "Prompt partial '{}' points outside of {}",
+                name,
+                dir.display()
+            );
+        }
+        if let Ok(content) = std::fs::read_to_string(path) {
+            return Ok(content.trim_end_matches('\n').to_string());
+        }
+    }
+    anyhow::bail!("Prompt partial '{}' not found in {}", name, dir.display())
+}
+
+fn render_at(
+    template: &str,
+    variables: &Variables,
+    dir: &Path,
+    depth: usize,
+) -> anyhow::Result<String> {
+    if depth > MAX_INCLUDE_DEPTH {
+        anyhow::bail!(
+            "Prompt partials are nested more than {} levels deep",
+            MAX_INCLUDE_DEPTH
+        );
+    }
+    let mut out = String::new();
+    for (text, tag) in parse(template)? {
+        out.push_str(text);
+        match tag {
+            Some(Tag::Variable(name)) => {
+                out.push_str(variables.get(name).map_or("", String::as_str))
+            }
+            Some(Tag::Include(name)) => {
+                let partial = read_partial(dir, name)?;
+                out.push_str(&render_at(&partial, variables, dir, depth + 1)?);
+            }
+            None => {}
+        }
+    }
+    Ok(out)
+}
+
+/// Substitutes `{{variable}}` tags and includes `{{> name}}` partials from `dir`
+pub fn render(template: &str, variables: &Variables, dir: &Path) -> anyhow::Result<String> {
+    render_at(template, variables, dir, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_render_variables() {
+        let mut variables = Variables::new();
+        variables.insert("branch", "feature/PROJ-1".to_string());
+        variables.insert("message", "fix it".to_string());
+        let dir = Path::new("/nonexistent");
+
+        assert_eq!(
+            render("On {{ branch }}: '{{message}}'", &variables, dir).unwrap(),
+            "On feature/PROJ-1: 'fix it'"
+        );
+        assert_eq!(render("[{{ticket}}]", &variables, dir).unwrap(), "[]");
+        assert_eq!(render("no tags", &variables, dir).unwrap(), "no tags");
+        assert!(render("{{nope}}", &variables, dir)
+            .unwrap_err()
+            .to_string()
+            .contains("Available: branch"));
+        assert!(check("{{branch").is_some());
+        assert_eq!(check("{{> rules}} {{language}}"), None);
+    }
+
+    #[test]
+    fn test_render_includes() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("rules.md"), "Rules for {{repo_name}}\n").unwrap();
+        std::fs::write(dir.path().join("outer"), "<{{> rules}}>").unwrap();
+        std::fs::write(dir.path().join("loop"), "{{> loop}}").unwrap();
+        let mut variables = Variables::new();
+        variables.insert("repo_name", "api".to_string());
+
+        assert_eq!(
+            render("{{> outer}}", &variables, dir.path()).unwrap(),
+            "<Rules for api>"
+        );
+        assert!(render("{{> missing}}", &variables, dir.path()).is_err());
+        assert!(render("{{> loop}}", &variables, dir.path())
+            .unwrap_err()
+            .to_string()
+            .contains("nested"));
+    }
+
+    #[test]
+    fn test_partial_stays_in_dir() {
+        let outside = tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.md"), "secret").unwrap();
+        let dir = tempdir().unwrap();
+        let variables = Variables::new();
+
+        let escape = format!("{{{{> {}}}}}", outside.path().join("secret").display());
+        for template in [
+            "{{> ../secret}}",
+            "{{> sub/rules}}",
+            "{{> ..}}",
+            escape.as_str(),
+        ] {
+            assert!(render(template, &variables, dir.path())
+                .unwrap_err()
+                .to_string()
+                .contains("Invalid prompt partial name"));
+        }
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(
+                outside.path().join("secret.md"),
+                dir.path().join("link.md"),
+            )
+            .unwrap();
+            assert!(render("{{> link}}", &variables, dir.path())
+                .unwrap_err()
+                .to_string()
+                .contains("outside"));
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::convention::ConventionConfig;
+use crate::openai::CommitSuggestion;
+use crate::trailers;
+
+/// Ticket placement modes accepted in the config and on the command line
+pub const MODES: &[&str] = &["off", "hint", "trailer", "prefix"];
+
+/// How ticket IDs are extracted from the branch name and added to the message
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct TicketConfig {
+    /// `off`, `hint` (tell the model), `trailer` or `prefix` (added deterministically)
+    pub mode: String,
+    /// Regexes matched against the branch name; the whole match is the ticket ID
+    pub patterns: Vec<String>,
+    /// Trailer token used in `trailer` mode, e.g. `Refs` or `Closes`
+    pub trailer: String,
+}
+
+impl Default for TicketConfig {
+    fn default() -> Self {
+        Self {
+            mode: String::from("off"),
+            patterns: vec![String::from(r"[A-Z][A-Z0-9]+-[0-9]+")],
+            trailer: String::from("Refs"),
+        }
+    }
+}
+
+/// Whether `message` mentions `id` as a whole token, so `PROJ-1` doesn't match `PROJ-12`
+fn mentions(message: &str, id: &str) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    message.match_indices(id).any(|(start, _)| {
+        let before = message[..start].chars().next_back();
+        let after = message[start + id.len()..].chars().next();
+        !before.is_some_and(is_word) && !after.is_some_and(is_word)
+    })
+}
+
+impl TicketConfig {
+    /// Returns the first invalid pattern together with its compile error
+    pub fn invalid_pattern(&self) -> Option<(String, regex::Error)> {
+        self.patterns
+            .iter()
+            .find_map(|p| Regex::new(p).err().map(|err| (p.clone(), err)))
+    }
+
+    /// Extracts unique ticket IDs from a branch name in order of appearance
+    pub fn extract(&self, branch: &str) -> Vec<String> {
+        let mut matches: Vec<(usize, String)> = self
+            .patterns
+            .iter()
+            .filter_map(|p| Regex::new(p).ok())
+            .flat_map(|re| {
+                re.find_iter(branch)
+                    .map(|m| (m.start(), m.as_str().to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        matches.sort_by_key(|(start, _)| *start);
+
+        let mut ids: Vec<String> = Vec::new();
+        for (_, id) in matches {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        ids
+    }
+
+    /// Builds the prompt hint used in `hint` mode
+    pub fn hint(&self, branch: &str, ids: &[String]) -> Option<String> {
+        if self.mode != "hint" || ids.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "The current branch '{}' references ticket(s) {}. Reference them in the commit message as a `{}: <id>` footer in the body.",
+            branch,
+            ids.join(", "),
+            self.trailer
+        ))
+    }
+
+    /// Adds the ticket IDs to a suggestion in `trailer` and `prefix` mode,
+    /// skipping IDs the message already mentions
+    pub fn apply(
+        &self,
+        convention: &ConventionConfig,
+        suggestion: &mut CommitSuggestion,
+        ids: &[String],
+    ) {
+        let message = suggestion.as_commit_message();
+        let missing: Vec<&String> = ids.iter().filter(|id| !mentions(&message, id)).collect();
+        if missing.is_empty() {
+            return;
+        }
+
+        match self.mode.as_str() {
+            "trailer" => {
+                let refs: Vec<String> = missing
+                    .iter()
+                    .map(|id| format!("{}: {}", self.trailer, id))
+                    .collect();
+                // Joins an existing trailer block instead of starting another
+                let message = trailers::append(&message, &refs);
+                suggestion.body = message.split_once("\n\n").map(|(_, body)| body.to_string());
+            }
+            "prefix" => {
+                let prefix = missing
+                    .iter()
+                    .map(|id| id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                // Keep headers valid for the convention by prefixing the subject
+                let title = suggestion.title.trim();
+                suggestion.title = match convention.parse_header(title) {
+                    Some(header) => format!(
+                        "{}{} {}",
+                        &title[..header.subject_start],
+                        prefix,
+                        &title[header.subject_start..]
+                    ),
+                    None => format!("{} {}", prefix, title),
+                };
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(mode: &str) -> TicketConfig {
+        TicketConfig {
+            mode: mode.to_string(),
+            patterns: vec![r"[A-Z][A-Z0-9]+-[0-9]+".to_string(), r"#[0-9]+".to_string()],
+            ..TicketConfig::default()
+        }
+    }
+
+    fn conventional() -> ConventionConfig {
+        ConventionConfig::default()
+    }
+
+    #[test]
+    fn test_extract() {
+        let config = config("trailer");
+        assert_eq!(
+            config.extract("feature/PROJ-123-fix-#456-and-PROJ-123"),
+            vec!["PROJ-123".to_string(), "#456".to_string()]
+        );
+        assert!(config.extract("main").is_empty());
+    }
+
+    #[test]
+    fn test_apply_trailer() {
+        let config = config("trailer");
+        let mut suggestion = CommitSuggestion {
+            title: "fix: bug".to_string(),
+            body: Some("Why it matters.".to_string()),
+        };
+        config.apply(&conventional(), &mut suggestion, &["PROJ-1".to_string()]);
+        assert_eq!(
+            suggestion.as_commit_message(),
+            "fix: bug\n\nWhy it matters.\n\nRefs: PROJ-1"
+        );
+
+        // Already referenced IDs aren't added twice
+        config.apply(&conventional(), &mut suggestion, &["PROJ-1".to_string()]);
+        assert_eq!(
+            suggestion.body.as_deref(),
+            Some("Why it matters.\n\nRefs: PROJ-1")
+        );
+
+        // A longer ID with the same prefix isn't a mention
+        config.apply(&conventional(), &mut suggestion, &["PROJ-12".to_string()]);
+        assert_eq!(
+            suggestion.body.as_deref(),
+            Some("Why it matters.\n\nRefs: PROJ-1\nRefs: PROJ-12")
+        );
+    }
+
+    #[test]
+    fn test_apply_prefix() {
+        let config = config("prefix");
+        let mut suggestion = CommitSuggestion {
+            title: "feat(api): add endpoint".to_string(),
+            body: None,
+        };
+        config.apply(&conventional(), &mut suggestion, &["PROJ-7".to_string()]);
+        assert_eq!(suggestion.title, "feat(api): PROJ-7 add endpoint");
+
+        let gitmoji = ConventionConfig {
+            preset: "gitmoji".to_string(),
+            ..ConventionConfig::default()
+        };
+        let mut suggestion = CommitSuggestion {
+            title: "✨ Add endpoint".to_string(),
+            body: None,
+        };
+        config.apply(&gitmoji, &mut suggestion, &["PROJ-7".to_string()]);
+        assert_eq!(suggestion.title, "✨ PROJ-7 Add endpoint");
+
+        // The subject doesn't have to end the title
+        let custom = ConventionConfig {
+            preset: "custom".to_string(),
+            title_format: "<description> [<type>]".to_string(),
+            ..ConventionConfig::default()
+        };
+        let mut suggestion = CommitSuggestion {
+            title: "Add endpoint [feat]".to_string(),
+            body: None,
+        };
+        config.apply(&custom, &mut suggestion, &["PROJ-7".to_string()]);
+        assert_eq!(suggestion.title, "PROJ-7 Add endpoint [feat]");
+    }
+
+    #[test]
+    fn test_hint_only_in_hint_mode() {
+        let ids = vec!["PROJ-7".to_string()];
+        assert!(config("trailer").hint("PROJ-7-x", &ids).is_none());
+        assert!(config("hint")
+            .hint("PROJ-7-x", &ids)
+            .unwrap()
+            .contains("PROJ-7"));
+    }
+}
@@ -0,0 +1,96 @@
+/// Splits a `Token: value` trailer into its token and value
+pub fn parse(trailer: &str) -> Option<(&str, &str)> {
+    let (token, value) = trailer.split_once(": ")?;
+    let valid_token =
+        !token.is_empty() && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    (valid_token && !value.trim().is_empty()).then_some((token, value.trim()))
+}
+
+pub fn co_author(identity: &str) -> String {
+    format!("Co-authored-by: {}", identity)
+}
+
+pub fn sign_off(identity: &str) -> String {
+    format!("Signed-off-by: {}", identity)
+}
+
+/// Whether every line of a paragraph is a trailer
+fn is_trailer_block(paragraph: &str) -> bool {
+    !paragraph.trim().is_empty() && paragraph.lines().all(|line| parse(line).is_some())
+}
+
+/// Appends trailers to a commit message, joining an existing trailer block and
+/// skipping trailers the message already contains
+pub fn append(message: &str, trailers: &[String]) -> String {
+    let message = message.trim_end();
+    let missing: Vec<&str> = trailers
+        .iter()
+        .map(|t| t.trim())
+        .filter(|t| !message.lines().any(|line| line.trim() == *t))
+        .fold(Vec::new(), |mut acc, t| {
+            if !acc.contains(&t) {
+                acc.push(t);
+            }
+            acc
+        });
+    if missing.is_empty() {
+        return message.to_string();
+    }
+
+    // The title alone never counts as a trailer block
+    let last_paragraph = message
+        .rsplit_once("\n\n")
+        .map(|(_, last)| last)
+        .unwrap_or("");
+    let separator = if is_trailer_block(last_paragraph) {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    format!("{}{}{}", message, separator, missing.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("Co-authored-by: A <a@b.c>"),
+            Some(("Co-authored-by", "A <a@b.c>"))
+        );
+        assert_eq!(parse("not a trailer"), None);
+        assert_eq!(parse("Bad token: x"), None);
+        assert_eq!(parse("Empty: "), None);
+    }
+
+    #[test]
+    fn test_append_new_block() {
+        assert_eq!(
+            append("fix: bug", &[sign_off("A <a@b.c>")]),
+            "fix: bug\n\nSigned-off-by: A <a@b.c>"
+        );
+        assert_eq!(
+            append("fix: bug\n\nWhy.", &[co_author("B <b@c.d>")]),
+            "fix: bug\n\nWhy.\n\nCo-authored-by: B <b@c.d>"
+        );
+    }
+
+    #[test]
+    fn test_append_joins_existing_block_and_skips_duplicates() {
+        let message = "fix: bug\n\nWhy.\n\nRefs: PROJ-1";
+        assert_eq!(
+            append(
+                message,
+                &[
+                    "Refs: PROJ-1".to_string(),
+                    sign_off("A <a@b.c>"),
+                    sign_off("A <a@b.c>"),
+                ]
+            ),
+            "fix: bug\n\nWhy.\n\nRefs: PROJ-1\nSigned-off-by: A <a@b.c>"
+        );
+        assert_eq!(append(message, &[]), message);
+    }
+}
@@ -1,22 +1,25 @@
+use std::path::PathBuf;
 use std::time::Duration;
 
 use colored::Colorize;
 use inquire::MultiSelect;
 
-use crate::{config::Config, git, jj, openai};
+use crate::output::{self, status};
+use crate::{cli, config::Config, diff_context::DiffContext, git, jj, language, openai, template};
 
 pub fn decide_diff(
     repo: &git2::Repository,
     used_tokens: usize,
     context: usize,
     always_select_files: bool,
+    diff_context: &DiffContext,
 ) -> anyhow::Result<(String, usize)> {
-    let staged_files = git::staged_files(&repo)?;
-    let mut diff = git::diff(&repo, &staged_files)?;
+    let staged_files = git::staged_files(repo)?;
+    let mut diff = git::diff(repo, &staged_files, diff_context)?;
     let mut diff_tokens = openai::count_token(&diff)?;
 
     if diff_tokens == 0 {
-        println!(
+        status!(
             "{} {}",
             "No staged files.".red(),
             "Please stage the files you want to commit.".bright_black()
@@ -25,14 +28,21 @@ pub fn decide_diff(
     }
 
     if always_select_files || used_tokens + diff_tokens > context {
+        if output::is_machine_readable() {
+            anyhow::bail!(
+                "The request is ~{} tokens long, while the maximum is {}. Select files interactively or stage fewer changes.",
+                used_tokens + diff_tokens,
+                context
+            );
+        }
         if always_select_files {
-            println!(
+            status!(
                 "{} {}",
                 "File selection mode:".blue(),
                 "Select the files you want to include in the commit.".bright_black()
             );
         } else {
-            println!(
+            status!(
                 "{} {}",
                 "The request is too long!".red(),
                 format!(
@@ -48,7 +58,7 @@ pub fn decide_diff(
             staged_files.clone(),
         )
         .prompt()?;
-        diff = git::diff(&repo, &selected_files)?;
+        diff = git::diff(repo, &selected_files, diff_context)?;
         diff_tokens = openai::count_token(&diff)?;
     }
     Ok((diff, diff_tokens))
@@ -59,9 +69,10 @@ pub fn decide_diff_jj(
     context: usize,
     always_select_files: bool,
     revision: Option<&str>,
+    diff_context: &DiffContext,
 ) -> anyhow::Result<(String, usize)> {
     let modified_files = jj::get_jj_modified_files()?;
-    let mut diff = jj::get_jj_diff(revision)?;
+    let mut diff = jj::get_jj_diff(revision, diff_context)?;
     let mut diff_tokens = openai::count_token(&diff)?;
 
     if diff_tokens == 0 {
@@ -70,7 +81,7 @@ pub fn decide_diff_jj(
         } else {
             "No changes detected.".to_string()
         };
-        println!(
+        status!(
             "{} {}",
             revision_msg.red(),
             "Please make some changes before running turbocommit.".bright_black()
@@ -79,14 +90,21 @@ pub fn decide_diff_jj(
     }
 
     if always_select_files || used_tokens + diff_tokens > context {
+        if output::is_machine_readable() {
+            anyhow::bail!(
+                "The request is ~{} tokens long, while the maximum is {}. Select files interactively or stage fewer changes.",
+                used_tokens + diff_tokens,
+                context
+            );
+        }
         if always_select_files {
-            println!(
+            status!(
                 "{} {}",
                 "File selection mode:".blue(),
                 "Select the files you want to include in the diff.".bright_black()
             );
         } else {
-            println!(
+            status!(
                 "{} {}",
                 "The request is too long!".red(),
                 format!(
@@ -104,28 +122,12 @@ pub fn decide_diff_jj(
         .prompt()?;
 
         // Get diff for selected files only
-        diff = jj::get_jj_diff_for_files(revision, &selected_files)?;
+        diff = jj::get_jj_diff_for_files(revision, &selected_files, diff_context)?;
         diff_tokens = openai::count_token(&diff)?;
     }
     Ok((diff, diff_tokens))
 }
 
-pub fn check_config_age(max_age: Duration) -> bool {
-    let path = Config::path();
-    let metadata = match std::fs::metadata(&path) {
-        Ok(metadata) => metadata,
-        Err(_) => {
-            return false;
-        }
-    };
-    let last_modified = metadata.modified().unwrap();
-    let now = std::time::SystemTime::now();
-    match now.duration_since(last_modified) {
-        Ok(duration) => duration > max_age,
-        Err(_) => false,
-    }
-}
-
 pub fn is_system_prompt_same_as_default(system_msg: &str) -> bool {
     let default = Config::default().system_msg;
     system_msg == default
@@ -151,12 +153,12 @@ pub async fn check_version() {
     let current_version = env!("CARGO_PKG_VERSION");
 
     if current_version != newest_version {
-        println!(
+        status!(
             "\n{} {}",
             "New version available!".yellow(),
             format!("v{}", newest_version).purple()
         );
-        println!(
+        status!(
             "To update, run\n{}",
             "cargo install --force turbocommit".purple()
         );
@@ -189,10 +191,41 @@ pub fn choose_message(choices: Vec<String>) -> Option<String> {
     Some(process_response(&choices[commit_index]))
 }
 
+/// Lets the user pick co-authors; cancelling picks none
+pub fn choose_co_authors(authors: Vec<String>) -> anyhow::Result<Vec<String>> {
+    if authors.is_empty() {
+        status!("{}", "No recent co-authors found.".bright_black());
+        return Ok(Vec::new());
+    }
+    match MultiSelect::new("Select co-authors:", authors).prompt() {
+        Ok(selected) => Ok(selected),
+        Err(inquire::InquireError::OperationCanceled) => Ok(Vec::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
 pub fn format_token_count(tokens: usize) -> String {
     format!("{:.2}k", tokens as f64 / 1000.0)
 }
 
+/// Resolves a configured path with `~` expanded to the home directory; an
+/// empty value is `name` in `~/.turbocommit`
+pub fn config_path(value: &str, name: &str) -> PathBuf {
+    let home = home::home_dir().unwrap_or_default();
+    if value.trim().is_empty() {
+        return home.join(".turbocommit").join(name);
+    }
+    match value.strip_prefix('~') {
+        Some(rest) => home.join(rest.trim_start_matches('/')),
+        None => PathBuf::from(value),
+    }
+}
+
+/// Lowercase hex encoding, e.g. of a digest
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 fn process_response(response: &str) -> String {
     // If response contains <think> tag, extract and process it
     if let Some(think_start) = response.find("<think>") {
@@ -202,11 +235,11 @@ fn process_response(response: &str) -> String {
             let message_part = response[think_end + 8..].trim_matches(|c: char| c.is_whitespace());
 
             // Print the thinking section nicely
-            println!("\n{}", "AI's Thought Process:".blue().bold());
-            println!("{}", thinking.bright_black());
-            println!("\n{}", "Generated Commit Message:".blue().bold());
-            println!("[0] {}", "=".repeat(76));
-            println!("{}", message_part);
+            status!("\n{}", "AI's Thought Process:".blue().bold());
+            status!("{}", thinking.bright_black());
+            status!("\n{}", "Generated Commit Message:".blue().bold());
+            status!("[0] {}", "=".repeat(76));
+            status!("{}", message_part);
 
             return message_part.to_string();
         }
@@ -216,3 +249,66 @@ fn process_response(response: &str) -> String {
         .trim_matches(|c: char| c.is_whitespace())
         .to_string()
 }
+
+/// Number of recent commit titles available as `{{recent_commits}}`
+const TEMPLATE_RECENT_COMMITS: usize = 5;
+
+/// Collects the values of the prompt template variables
+pub fn template_variables(
+    options: &cli::Options,
+    vcs_type: &jj::VcsType,
+    branch: Option<&str>,
+    ticket_ids: &[String],
+    patch_files: &[String],
+) -> anyhow::Result<template::Variables> {
+    let (files, commits) = match vcs_type {
+        jj::VcsType::Git => {
+            let repo = git::get_repo()?;
+            let files = if options.amend {
+                git::last_commit_files(&repo)?
+            } else {
+                git::staged_files(&repo)?
+            };
+            let commits = git::recent_commit_messages(
+                &repo,
+                TEMPLATE_RECENT_COMMITS,
+                usize::from(options.amend),
+            )?;
+            (files, commits)
+        }
+        jj::VcsType::Jujutsu => (
+            jj::get_jj_modified_files()?,
+            jj::get_jj_recent_descriptions(
+                options.jj_revision.as_deref(),
+                TEMPLATE_RECENT_COMMITS,
+            )?,
+        ),
+        jj::VcsType::Patch => (patch_files.to_vec(), Vec::new()),
+    };
+    let repo_name = jj::repo_root()
+        .and_then(|root| {
+            root.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .unwrap_or_default();
+    let titles: Vec<&str> = commits
+        .iter()
+        .filter_map(|message| message.lines().next())
+        .collect();
+
+    let mut variables = template::Variables::new();
+    variables.insert("branch", branch.unwrap_or_default().to_string());
+    variables.insert("repo_name", repo_name);
+    variables.insert("staged_files", files.join("\n"));
+    variables.insert("recent_commits", titles.join("\n"));
+    variables.insert(
+        "language",
+        if language::is_default(&options.language) {
+            String::from("English")
+        } else {
+            language::name(&options.language)
+        },
+    );
+    variables.insert("ticket", ticket_ids.join(", "));
+    Ok(variables)
+}